@@ -0,0 +1,273 @@
+//! Timing for the registered solutions, with the generator and solver measured separately.
+//!
+//! Results are kept as CSV so that a run can be saved as a baseline and later runs compared with it.
+
+use std::{collections::HashMap, fmt::Display, time::Duration};
+
+use anyhow::{ensure, Context, Result};
+use itertools::Itertools;
+
+use crate::registry::Solution;
+
+const CSV_HEADER: &str = "name,runs,generator_median_ns,generator_min_ns,generator_max_ns,runner_median_ns,runner_min_ns,runner_max_ns";
+
+/// Summary of repeated timings of the same thing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn new(samples: &[Duration]) -> Self {
+        assert!(!samples.is_empty(), "Need at least one sample");
+        let sorted = samples.iter().copied().sorted().collect_vec();
+        let mid = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 0 {
+            (sorted[mid - 1] + sorted[mid]) / 2
+        } else {
+            sorted[mid]
+        };
+        Self {
+            median,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    pub fn spread(&self) -> Duration {
+        self.max - self.min
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    pub name: String,
+    pub runs: usize,
+    pub generator: Stats,
+    pub runner: Stats,
+}
+
+impl Measurement {
+    pub fn total(&self) -> Duration {
+        self.generator.median + self.runner.median
+    }
+
+    fn to_csv(&self) -> String {
+        let nanos = |d: Duration| d.as_nanos().to_string();
+        [
+            self.name.clone(),
+            self.runs.to_string(),
+            nanos(self.generator.median),
+            nanos(self.generator.min),
+            nanos(self.generator.max),
+            nanos(self.runner.median),
+            nanos(self.runner.min),
+            nanos(self.runner.max),
+        ]
+        .join(",")
+    }
+
+    fn from_csv(line: &str) -> Result<Self> {
+        let fields = line.split(',').collect_vec();
+        ensure!(
+            fields.len() == 8,
+            "Expected 8 fields but found {}",
+            fields.len()
+        );
+        let nanos = |idx: usize| -> Result<Duration> {
+            Ok(Duration::from_nanos(
+                fields[idx]
+                    .parse()
+                    .with_context(|| format!("Bad time {:?}", fields[idx]))?,
+            ))
+        };
+        Ok(Self {
+            name: fields[0].to_string(),
+            runs: fields[1].parse().context("Bad run count")?,
+            generator: Stats {
+                median: nanos(2)?,
+                min: nanos(3)?,
+                max: nanos(4)?,
+            },
+            runner: Stats {
+                median: nanos(5)?,
+                min: nanos(6)?,
+                max: nanos(7)?,
+            },
+        })
+    }
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: generator {:?} (±{:?}), runner {:?} (±{:?})",
+            self.name,
+            self.generator.median,
+            self.generator.spread(),
+            self.runner.median,
+            self.runner.spread()
+        )
+    }
+}
+
+/// Generates and solves `input` `runs` times, failing if any run fails
+pub fn measure(solution: &Solution, input: &str, runs: usize) -> Result<Measurement> {
+    ensure!(runs > 0, "Need at least one run");
+    let (generator, runner): (Vec<_>, Vec<_>) = (0..runs)
+        .map(|_| solution.run(input).map(|o| (o.generator, o.runner)))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+    Ok(Measurement {
+        name: solution.name().to_string(),
+        runs,
+        generator: Stats::new(&generator),
+        runner: Stats::new(&runner),
+    })
+}
+
+pub fn to_csv(measurements: &[Measurement]) -> String {
+    std::iter::once(CSV_HEADER.to_string())
+        .chain(measurements.iter().map(Measurement::to_csv))
+        .map(|l| l + "\n")
+        .collect()
+}
+
+pub fn from_csv(text: &str) -> Result<Vec<Measurement>> {
+    text.lines()
+        .enumerate()
+        .filter(|(idx, l)| *idx > 0 || *l != CSV_HEADER)
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(idx, l)| Measurement::from_csv(l).with_context(|| format!("Line {}", idx + 1)))
+        .collect()
+}
+
+/// How a measurement compares with the same solution in the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub name: String,
+    pub baseline: Duration,
+    pub current: Duration,
+    /// Relative change in the total median, so `0.1` is 10% slower
+    pub change: f64,
+    pub regressed: bool,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:?} -> {:?} ({:+.1}%){}",
+            self.name,
+            self.baseline,
+            self.current,
+            self.change * 100.0,
+            if self.regressed { " REGRESSED" } else { "" }
+        )
+    }
+}
+
+/// Compares the total median time of everything also in `baseline`.
+/// Anything more than `threshold` slower (as a fraction) counts as a regression.
+pub fn compare(
+    current: &[Measurement],
+    baseline: &[Measurement],
+    threshold: f64,
+) -> Vec<Comparison> {
+    let baseline: HashMap<&str, &Measurement> =
+        baseline.iter().map(|m| (m.name.as_str(), m)).collect();
+    current
+        .iter()
+        .filter_map(|m| {
+            let base = baseline.get(m.name.as_str())?;
+            let change = if base.total().is_zero() {
+                0.0
+            } else {
+                m.total().as_secs_f64() / base.total().as_secs_f64() - 1.0
+            };
+            Some(Comparison {
+                name: m.name.clone(),
+                baseline: base.total(),
+                current: m.total(),
+                change,
+                regressed: change > threshold,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registry;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_millis(v)).collect()
+    }
+
+    fn measurement(name: &str, generator: u64, runner: u64) -> Measurement {
+        Measurement {
+            name: name.to_string(),
+            runs: 3,
+            generator: Stats::new(&ms(&[generator])),
+            runner: Stats::new(&ms(&[runner, runner + 4])),
+        }
+    }
+
+    #[test]
+    fn stats_test() {
+        let stats = Stats::new(&ms(&[5, 1, 9]));
+        assert_eq!(stats.median, Duration::from_millis(5));
+        assert_eq!(stats.spread(), Duration::from_millis(8));
+        assert_eq!(
+            Stats::new(&ms(&[4, 1, 2, 8])).median,
+            Duration::from_millis(3)
+        );
+    }
+
+    #[test]
+    fn csv_test() -> Result<()> {
+        let measurements = vec![
+            measurement("day1_part1", 1, 2),
+            measurement("day14_part1_fast", 3, 4),
+        ];
+        let text = to_csv(&measurements);
+        assert!(text.starts_with("name,runs,"));
+        assert_eq!(from_csv(&text)?, measurements);
+
+        let err = from_csv(&format!("{}day1_part1,3,1\n", text)).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Line 4: Expected 8 fields but found 3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compare_test() -> Result<()> {
+        let baseline = vec![
+            measurement("day1_part1", 10, 10),
+            measurement("day1_part2", 10, 10),
+        ];
+        let current = vec![
+            measurement("day1_part1", 10, 11),
+            measurement("day1_part2", 10, 20),
+            measurement("day2_part1", 1, 1),
+        ];
+        let comparisons = compare(&current, &baseline, 0.1);
+        assert_eq!(comparisons.len(), 2);
+        assert!(!comparisons[0].regressed);
+        assert!(comparisons[1].regressed);
+        assert!((comparisons[1].change - 10.0 / 22.0).abs() < 1e-9);
+
+        let solution = registry::find(1, Some(1)).next().context("No day 1")?;
+        let result = measure(solution, "1000\n\n2000", 3)?;
+        assert_eq!((result.name.as_str(), result.runs), ("day1_part1", 3));
+        assert!(result.generator.min <= result.generator.median);
+        Ok(())
+    }
+}
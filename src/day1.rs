@@ -1,52 +1,143 @@
-use anyhow::{Context, Result};
+use std::{cmp::Reverse, collections::BinaryHeap, io::BufRead, sync::Arc};
+
+use anyhow::{bail, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Itertools;
 
-type Input = Vec<Vec<i32>>;
+type Input = Vec<ElfStats>;
 type Output = i32;
 
-#[aoc_generator(day1)]
-fn input_generator(input: &str) -> Result<Input> {
-    let mut result: Vec<Vec<i32>> = vec![];
-    let mut lines = input.lines();
-    loop {
-        let l = lines.next();
-        if l.is_none() {
-            return Ok(result);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ElfStats {
+    count: usize,
+    sum: i32,
+    max: i32,
+    /// The lower of the two middle items when there are an even number of them
+    median: i32,
+}
+
+impl ElfStats {
+    fn new(mut items: Vec<i32>) -> Option<Self> {
+        items.sort_unstable();
+        Some(Self {
+            count: items.len(),
+            sum: items.iter().sum(),
+            max: *items.last()?,
+            median: items[(items.len() - 1) / 2],
+        })
+    }
+}
+
+/// Reads elves one at a time, only holding the items of the current elf.
+struct ElfReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    line_no: usize,
+}
+
+impl<R: BufRead> ElfReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_no: 0,
+        }
+    }
+
+    fn next_elf(&mut self) -> Result<Option<ElfStats>> {
+        let mut items = vec![];
+        for line in self.lines.by_ref() {
+            self.line_no += 1;
+            let line = line.with_context(|| format!("Line {}", self.line_no))?;
+            let line = line.trim();
+            if line.is_empty() {
+                if items.is_empty() {
+                    bail!("Line {}: Blank line without an elf before it", self.line_no);
+                }
+                break;
+            }
+            items.push(
+                line.parse()
+                    .with_context(|| format!("Line {}: Bad calories {:?}", self.line_no, line))?,
+            );
+        }
+        Ok(ElfStats::new(items))
+    }
+}
+
+impl<R: BufRead> Iterator for ElfReader<R> {
+    type Item = Result<ElfStats>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_elf().transpose()
+    }
+}
+
+/// The `k` largest values, largest first, keeping at most `k` of them at a time.
+fn top_k(values: impl IntoIterator<Item = i32>, k: usize) -> Vec<i32> {
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for v in values {
+        heap.push(Reverse(v));
+        if heap.len() > k {
+            heap.pop();
         }
-        let mut l = l.unwrap();
-        let mut elf = vec![];
-        while !l.is_empty() {
-            elf.push(l.parse()?);
-            l = lines.next().unwrap_or_default();
+    }
+    heap.into_sorted_vec().into_iter().map(|r| r.0).collect()
+}
+
+/// The calories carried by the `k` best supplied elves, streamed from `reader`
+fn top_calories<R: BufRead>(reader: R, k: usize) -> Result<Vec<i32>> {
+    let mut error = None;
+    let sums = ElfReader::new(reader).map_while(|elf| match elf {
+        Ok(elf) => Some(elf.sum),
+        Err(e) => {
+            error = Some(e);
+            None
         }
-        result.push(elf);
+    });
+    let result = top_k(sums, k);
+    match error {
+        Some(e) => Err(e),
+        None => Ok(result),
     }
 }
 
+#[aoc_generator(day1)]
+fn input_generator(input: &str) -> Result<Input> {
+    ElfReader::new(input.as_bytes()).collect()
+}
+
 #[aoc(day1, part1)]
 fn part1(input: &Input) -> Result<Output> {
-    let m = input
-        .iter()
-        .map(|elf| elf.iter().sum())
-        .max()
-        .context("No data?")?;
+    let m = input.iter().map(|elf| elf.sum).max().context("No data?")?;
 
     Ok(m)
 }
 
 #[aoc(day1, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let result = input
-        .iter()
-        .map(|elf| elf.iter().sum())
-        .sorted_unstable_by(|a: &i32, b: &i32| b.cmp(a))
-        .take(3)
-        .sum();
+    let result = top_k(input.iter().map(|elf| elf.sum), 3).iter().sum();
 
     Ok(result)
 }
 
+/// The streaming variants read the elves themselves, so only need a shared handle on the raw text
+#[aoc_generator(day1, part1, streaming)]
+#[aoc_generator(day1, part2, streaming)]
+fn raw_generator(input: &Arc<str>) -> Arc<str> {
+    Arc::clone(input)
+}
+
+#[aoc(day1, part1, streaming)]
+fn part1_streaming(input: &Arc<str>) -> Result<Output> {
+    top_calories(input.as_bytes(), 1)?
+        .first()
+        .copied()
+        .context("No data?")
+}
+
+#[aoc(day1, part2, streaming)]
+fn part2_streaming(input: &Arc<str>) -> Result<Output> {
+    Ok(top_calories(input.as_bytes(), 3)?.iter().sum())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -57,6 +148,7 @@ mod test {
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, 24000);
+        assert_eq!(part1_streaming(&raw_generator(&INPUT_STR.into()))?, 24000);
         Ok(())
     }
 
@@ -64,6 +156,53 @@ mod test {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 45000);
+        assert_eq!(part2_streaming(&raw_generator(&INPUT_STR.into()))?, 45000);
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        assert_eq!(
+            input[2],
+            ElfStats {
+                count: 2,
+                sum: 11000,
+                max: 6000,
+                median: 5000
+            }
+        );
+        assert_eq!(input[3].median, 8000);
+        assert_eq!(
+            top_k(input.iter().map(|elf| elf.sum), 2),
+            vec![24000, 11000]
+        );
+        assert_eq!(top_k(vec![1, 2], 5), vec![2, 1]);
+
+        assert_eq!(
+            top_calories(INPUT_STR.as_bytes(), 3)?,
+            vec![24000, 11000, 10000]
+        );
+        assert_eq!(top_calories("1\n2\n".as_bytes(), 1)?, vec![3]);
+
+        let path = std::env::temp_dir().join(format!("aoc_2022_day1_{}.txt", std::process::id()));
+        std::fs::write(&path, INPUT_STR)?;
+        let file = std::fs::File::open(&path)?;
+        // A buffer smaller than a line makes the reader refill it part way through lines
+        let top = top_calories(std::io::BufReader::with_capacity(3, file), 3);
+        std::fs::remove_file(&path)?;
+        assert_eq!(top?, vec![24000, 11000, 10000]);
+
+        let err = top_calories("1\n\n\n2".as_bytes(), 1).unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "Line 3: Blank line without an elf before it"
+        );
+        let err = input_generator("1\n\n2\nabc").unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Line 4: Bad calories \"abc\": invalid digit found in string"
+        );
         Ok(())
     }
 }
@@ -1,59 +1,56 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
 
 use anyhow::{bail, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
+use colored::{Color, Colorize};
 use itertools::Itertools;
 use salusa_aoc::{Graph, MatrixTranspose};
 
-type Coord = (i32, i32);
+use crate::grid::{BoundingBox, Compass, Grid, Point, Point2};
+
+/// `x` indexes the outer `Vec` of the heights and `y` the inner one
+type Coord = Point2<i32>;
 type Input = (Graph<Coord, u32>, Coord, Coord, Vec<Vec<i8>>);
 type Output = u32;
 
 #[aoc_generator(day12)]
 fn input_generator(input: &str) -> Result<Input> {
-    let elems = input
-        .lines()
-        .map(|l| l.trim().chars().collect_vec())
-        .collect_vec();
-
-    let mut start = Coord::default();
-    let mut end = Coord::default();
-
-    let mut heights = vec![vec![0i8; elems.len()]; elems[0].len()];
-    for (y, row) in elems.iter().enumerate() {
-        for (x, e) in row.iter().enumerate() {
-            let height = match e {
-                'S' => {
-                    start = (x as i32, y as i32);
-                    1
-                }
-                'E' => {
-                    end = (x as i32, y as i32);
-                    26
-                }
-                _ => *e as i8 + 1i8 - b'a' as i8,
-            };
-            heights[x][y] = height;
-        }
+    let input = input.lines().map(str::trim).join("\n");
+    let mut start = None;
+    let mut end = None;
+    let grid = Grid::parse_dense(&input, |p, c| {
+        Ok(match c {
+            'S' => {
+                start = Some(p);
+                1
+            }
+            'E' => {
+                end = Some(p);
+                26
+            }
+            'a'..='z' => c as i8 + 1i8 - b'a' as i8,
+            _ => bail!("Unexpected height {:?} at {}", c, p),
+        })
+    })?;
+    let (start, end) = (start.context("No start")?, end.context("No end")?);
+
+    let size = grid.bounding_box().context("Empty map")?.size();
+    let mut heights = vec![vec![0i8; size.y() as usize]; size.x() as usize];
+    for (p, height) in grid.iter() {
+        heights[p.x() as usize][p.y() as usize] = *height;
     }
 
-    let max_x = heights.len() - 1;
-    let max_y = heights[0].len() - 1;
     let mut graph: Graph<Coord, u32> = Graph::new(false);
     for (x, col) in heights.iter().enumerate() {
         for (y, curr_height) in col.iter().enumerate() {
-            // let curr_height = heights[x][y];
-            if x > 0 && can_move(*curr_height, heights[x - 1][y]) {
-                graph.add_edge((x as i32, y as i32), (x as i32 - 1, y as i32));
-            }
-            if x < max_x && can_move(*curr_height, heights[x + 1][y]) {
-                graph.add_edge((x as i32, y as i32), (x as i32 + 1, y as i32));
-            }
-            if y > 0 && can_move(*curr_height, heights[x][y - 1]) {
-                graph.add_edge((x as i32, y as i32), (x as i32, y as i32 - 1));
-            }
-            if y < max_y && can_move(*curr_height, heights[x][y + 1]) {
-                graph.add_edge((x as i32, y as i32), (x as i32, y as i32 + 1));
+            let curr = Point([x as i32, y as i32]);
+            for next in neighbours(curr, size, &Compass::ORTHOGONAL) {
+                if can_move(*curr_height, heights[next.x() as usize][next.y() as usize]) {
+                    graph.add_edge(curr, next);
+                }
             }
         }
     }
@@ -65,6 +62,196 @@ fn can_move(curr_height: i8, end_height: i8) -> bool {
     curr_height >= end_height || end_height - curr_height == 1
 }
 
+/// Neighbours of `coord`, one step along each of `moves`, which lie within a grid of `size`
+fn neighbours(coord: Coord, size: Coord, moves: &'static [Compass]) -> impl Iterator<Item = Coord> {
+    let bounds = BoundingBox {
+        min: Point([0, 0]),
+        max: size - Point([1, 1]),
+    };
+    moves
+        .iter()
+        .map(move |m| m.step(&coord))
+        .filter(move |c| bounds.contains(c))
+}
+
+/// Weighted, directed graph over the cells of a height map
+#[derive(Debug, Clone)]
+struct GridGraph {
+    edges: HashMap<Coord, Vec<(Coord, u32)>>,
+    moves: &'static [Compass],
+}
+
+impl GridGraph {
+    /// `cost` is given the heights of both ends of a step and returns `None` if the step isn't allowed
+    fn new<F>(heights: &[Vec<i8>], moves: &'static [Compass], cost: F) -> Self
+    where
+        F: Fn(i8, i8) -> Option<u32>,
+    {
+        let size = Point([heights.len() as i32, heights[0].len() as i32]);
+        let mut edges = HashMap::new();
+        for (x, col) in heights.iter().enumerate() {
+            for (y, curr_height) in col.iter().enumerate() {
+                let curr = Point([x as i32, y as i32]);
+                let steps = neighbours(curr, size, moves)
+                    .filter_map(|next| {
+                        cost(*curr_height, heights[next.x() as usize][next.y() as usize])
+                            .map(|c| (next, c))
+                    })
+                    .collect();
+                edges.insert(curr, steps);
+            }
+        }
+        Self { edges, moves }
+    }
+
+    /// Cheapest path from `start` to any node matching `is_goal`, along with its cost.
+    /// `heuristic` must never overestimate the remaining cost.
+    fn search<G, H>(&self, start: Coord, is_goal: G, heuristic: H) -> Option<(u32, Vec<Coord>)>
+    where
+        G: Fn(&Coord) -> bool,
+        H: Fn(&Coord) -> u32,
+    {
+        let mut best: HashMap<Coord, u32> = HashMap::new();
+        let mut previous: HashMap<Coord, Coord> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        best.insert(start, 0);
+        queue.push(Reverse((heuristic(&start), 0, start)));
+
+        while let Some(Reverse((_, cost, node))) = queue.pop() {
+            if best.get(&node).is_some_and(|b| *b < cost) {
+                continue;
+            }
+            if is_goal(&node) {
+                let mut path = vec![node];
+                while let Some(prev) = previous.get(path.last()?) {
+                    path.push(*prev);
+                }
+                path.reverse();
+                return Some((cost, path));
+            }
+            for (next, edge_cost) in self.edges.get(&node).into_iter().flatten() {
+                let next_cost = cost + edge_cost;
+                if best.get(next).is_some_and(|b| *b <= next_cost) {
+                    continue;
+                }
+                best.insert(*next, next_cost);
+                previous.insert(*next, node);
+                queue.push(Reverse((next_cost + heuristic(next), next_cost, *next)));
+            }
+        }
+        None
+    }
+
+    fn dijkstra<G>(&self, start: Coord, is_goal: G) -> Option<(u32, Vec<Coord>)>
+    where
+        G: Fn(&Coord) -> bool,
+    {
+        self.search(start, is_goal, |_| 0)
+    }
+
+    /// Uses the number of steps to `end` as the heuristic, so every edge must cost at least 1
+    fn astar(&self, start: Coord, end: Coord) -> Option<(u32, Vec<Coord>)> {
+        let dist = |c: &Coord| {
+            let steps = if self.moves.iter().any(Compass::is_diagonal) {
+                c.chebyshev(&end)
+            } else {
+                c.manhattan(&end)
+            };
+            steps as u32
+        };
+        self.search(start, |c| *c == end, dist)
+    }
+}
+
+fn climb_cost(curr_height: i8, end_height: i8) -> Option<u32> {
+    can_move(curr_height, end_height).then_some(1)
+}
+
+/// Breadth-first search which remembers how it reached each node, so the route can be recovered
+fn bfs_path<G>(graph: &Graph<Coord, u32>, start: Coord, is_goal: G) -> Option<Vec<Coord>>
+where
+    G: Fn(&Coord) -> bool,
+{
+    let mut previous: HashMap<Coord, Option<Coord>> = HashMap::new();
+    previous.insert(start, None);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        if is_goal(&node) {
+            let mut path = vec![node];
+            while let Some(Some(prev)) = previous.get(path.last()?) {
+                path.push(*prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for e in graph.edges(&node) {
+            previous.entry(e).or_insert_with(|| {
+                queue.push_back(e);
+                Some(node)
+            });
+        }
+    }
+    None
+}
+
+fn arrow(from: &Coord, to: &Coord) -> char {
+    let step = *to - *from;
+    match (step.x(), step.y()) {
+        (1, 0) => '>',
+        (-1, 0) => '<',
+        (0, 1) => 'v',
+        (0, -1) => '^',
+        _ => '*',
+    }
+}
+
+/// Draws the route as in the puzzle text, with each step marked by an arrow towards the next one.
+/// When `colour` is set, every cell is shaded from dark green (a) to white (z) by its height.
+fn render_path(heights: &[Vec<i8>], path: &[Coord], colour: bool) -> String {
+    let mut symbols: HashMap<Coord, char> = path
+        .iter()
+        .tuple_windows()
+        .map(|(from, to)| (*from, arrow(from, to)))
+        .collect();
+    if let Some(end) = path.last() {
+        symbols.insert(*end, 'E');
+    }
+
+    let mut result = String::new();
+    for y in 0..heights[0].len() {
+        for (x, col) in heights.iter().enumerate() {
+            let on_path = symbols.get(&Point([x as i32, y as i32]));
+            let sym = on_path.copied().unwrap_or('.').to_string();
+            if colour {
+                let shade = (col[y] - 1).clamp(0, 25) as u32;
+                let color = Color::TrueColor {
+                    r: (shade * 255 / 25) as u8,
+                    g: (100 + shade * 155 / 25) as u8,
+                    b: (shade * 255 / 25) as u8,
+                };
+                if on_path.is_some() {
+                    result += &format!("{}", sym.color(color).bold());
+                } else {
+                    result += &format!("{}", sym.color(color));
+                }
+            } else {
+                result += &sym;
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Draws a shortest route from the start to the end of the height map in `input`
+pub fn render_route(input: &str, colour: bool) -> Result<String> {
+    let input = input_generator(input)?;
+    let path = bfs_path(&input.0, input.1, |c| *c == input.2).context("No path found")?;
+    Ok(render_path(&input.3, &path, colour))
+}
+
 #[aoc(day12, part1)]
 #[allow(clippy::map_entry)]
 fn part1(input: &Input) -> Result<Output> {
@@ -103,6 +290,13 @@ fn part1_provided(input: &Input) -> Result<Output> {
     dists.get(&input.2).copied().context("no path found")
 }
 
+#[aoc(day12, part1, astar)]
+fn part1_astar(input: &Input) -> Result<Output> {
+    let graph = GridGraph::new(&input.3, &Compass::ORTHOGONAL, climb_cost);
+    let (cost, _path) = graph.astar(input.1, input.2).context("No path found")?;
+    Ok(cost)
+}
+
 #[aoc(day12, part2)]
 #[allow(clippy::map_entry)]
 fn part2(input: &Input) -> Result<Output> {
@@ -127,7 +321,7 @@ fn part2(input: &Input) -> Result<Output> {
             if !dists.contains_key(&e) {
                 dists.insert(e, my_dist + 1);
                 queue.push_back(e);
-                if heights[e.0 as usize][e.1 as usize] == 1 && my_dist + 1 < best_dist {
+                if heights[e.x() as usize][e.y() as usize] == 1 && my_dist + 1 < best_dist {
                     // println!("Found new best {} at {:?}", my_dist + 1, e);
                     best_dist = my_dist + 1;
                 }
@@ -147,7 +341,7 @@ fn part2_provided(input: &Input) -> Result<Output> {
     dists
         .iter()
         .flat_map(|(coord, dist)| {
-            if heights[coord.0 as usize][coord.1 as usize] == 1 {
+            if heights[coord.x() as usize][coord.y() as usize] == 1 {
                 Some(dist)
             } else {
                 None
@@ -158,6 +352,19 @@ fn part2_provided(input: &Input) -> Result<Output> {
         .context("None found")
 }
 
+#[aoc(day12, part2, dijkstra)]
+fn part2_dijkstra(input: &Input) -> Result<Output> {
+    // Walk backwards from the end, so every step is reversed
+    let heights = &input.3;
+    let graph = GridGraph::new(heights, &Compass::ORTHOGONAL, |curr, end| {
+        climb_cost(end, curr)
+    });
+    let (cost, _path) = graph
+        .dijkstra(input.2, |c| heights[c.x() as usize][c.y() as usize] == 1)
+        .context("None found")?;
+    Ok(cost)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -173,6 +380,7 @@ mod test {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, 31);
         assert_eq!(part1_provided(&input)?, 31);
+        assert_eq!(part1_astar(&input)?, 31);
         Ok(())
     }
 
@@ -181,6 +389,72 @@ mod test {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 29);
         assert_eq!(part2_provided(&input)?, 29);
+        assert_eq!(part2_dijkstra(&input)?, 29);
+        Ok(())
+    }
+
+    #[test]
+    fn grid_graph_test() -> Result<()> {
+        let (_, start, end, heights) = input_generator(INPUT_STR)?;
+        let height = |c: &Coord| heights[c.x() as usize][c.y() as usize];
+
+        let graph = GridGraph::new(&heights, &Compass::ORTHOGONAL, climb_cost);
+        let (cost, path) = graph.astar(start, end).context("No path")?;
+        assert_eq!(cost, 31);
+        assert_eq!(path.len(), 32);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        for (a, b) in path.iter().tuple_windows() {
+            assert_eq!(a.manhattan(b), 1);
+            assert!(can_move(height(a), height(b)));
+        }
+        assert_eq!(graph.dijkstra(start, |c| *c == end).map(|r| r.0), Some(31));
+
+        // Every step up costs an extra 10 and the end is 25 steps higher than the start
+        let penalty =
+            |curr: i8, end: i8| climb_cost(curr, end).map(|c| c + 10 * (end - curr).max(0) as u32);
+        let graph = GridGraph::new(&heights, &Compass::ORTHOGONAL, penalty);
+        let (cost, path) = graph.astar(start, end).context("No path")?;
+        assert_eq!(cost, 31 + 250);
+        assert_eq!(
+            graph.dijkstra(start, |c| *c == end).map(|r| r.0),
+            Some(cost)
+        );
+        assert_eq!(path.len(), 32);
+
+        let graph = GridGraph::new(&heights, &Compass::ALL, climb_cost);
+        let (cost, path) = graph.astar(start, end).context("No path")?;
+        assert!(cost < 31);
+        assert_eq!(
+            graph.dijkstra(start, |c| *c == end).map(|r| r.0),
+            Some(cost)
+        );
+        for (a, b) in path.iter().tuple_windows() {
+            assert_eq!(a.chebyshev(b), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn render_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        let path = bfs_path(&input.0, input.1, |c| *c == input.2).context("No path")?;
+        assert_eq!(path.len(), 32);
+        assert_eq!(path.first(), Some(&input.1));
+
+        let rendered = render_path(&input.3, &path, false);
+        let lines = rendered.lines().collect_vec();
+        assert_eq!(lines.len(), 5);
+        assert!(lines.iter().all(|l| l.len() == 8));
+        assert_eq!(lines[2].chars().nth(5), Some('E'));
+        assert_ne!(lines[0].chars().next(), Some('.'));
+        assert_eq!(rendered.chars().filter(|c| "<>^v".contains(*c)).count(), 31);
+        assert_eq!(rendered.matches('.').count(), 40 - 32);
+
+        // Ties between equally short routes may be broken differently
+        let route = render_route(INPUT_STR, false)?;
+        assert_eq!(route.lines().nth(2).and_then(|l| l.find('E')), Some(5));
+        assert_eq!(route.matches('.').count(), 40 - 32);
         Ok(())
     }
 }
@@ -1,14 +1,14 @@
-use std::{cmp::Ordering, fmt::Display, str::FromStr};
+use std::{cmp::Ordering, fmt::Display, io::BufRead, str::FromStr, sync::Arc};
 
 use anyhow::{bail, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 use nom::{
     branch::alt,
-    character::{self, complete::i32},
-    combinator::map,
+    character::complete::{char, i64, multispace0},
+    combinator::{all_consuming, map},
     multi::separated_list0,
-    sequence::delimited,
+    sequence::{delimited, pair, tuple},
     IResult,
 };
 
@@ -17,7 +17,7 @@ type Output = i32;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Packet {
-    Number(i32),
+    Number(i64),
     List(Vec<Packet>),
 }
 
@@ -25,9 +25,15 @@ impl FromStr for Packet {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match nom_packet_list(s) {
+        match all_consuming(delimited(multispace0, nom_packet_list, multispace0))(s) {
             Ok((_, i)) => Ok(i),
-            Err(e) => bail!("Error parsing {}", e),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => bail!(
+                "Error parsing packet at byte {}: {:?} in {:?}",
+                s.len() - e.input.len(),
+                e.code,
+                s
+            ),
+            Err(e) => bail!("Error parsing packet: {}", e),
         }
     }
 }
@@ -62,16 +68,152 @@ impl Display for Packet {
     }
 }
 
+/// Why a comparison stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    LeftSmaller,
+    RightSmaller,
+    LeftRanOut,
+    RightRanOut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Compare(Packet, Packet),
+    /// A number on the left was wrapped into this list
+    ConvertLeft(Packet),
+    /// A number on the right was wrapped into this list
+    ConvertRight(Packet),
+    Decided(Decision),
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Compare(left, right) => write!(f, "Compare {} vs {}", left, right),
+            Step::ConvertLeft(p) => {
+                write!(f, "Mixed types; convert left to {} and retry comparison", p)
+            }
+            Step::ConvertRight(p) => write!(
+                f,
+                "Mixed types; convert right to {} and retry comparison",
+                p
+            ),
+            Step::Decided(Decision::LeftSmaller) => {
+                write!(f, "Left side is smaller, so inputs are in the right order")
+            }
+            Step::Decided(Decision::RightSmaller) => {
+                write!(
+                    f,
+                    "Right side is smaller, so inputs are not in the right order"
+                )
+            }
+            Step::Decided(Decision::LeftRanOut) => {
+                write!(
+                    f,
+                    "Left side ran out of items, so inputs are in the right order"
+                )
+            }
+            Step::Decided(Decision::RightRanOut) => {
+                write!(
+                    f,
+                    "Right side ran out of items, so inputs are not in the right order"
+                )
+            }
+        }
+    }
+}
+
+/// Every step taken while comparing two packets, along with its nesting depth
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Explanation {
+    steps: Vec<(usize, Step)>,
+    result: Ordering,
+}
+
+impl Explanation {
+    /// The step which settled the order, or `None` if the packets are equal
+    fn deciding_step(&self) -> Option<&Step> {
+        self.steps
+            .last()
+            .map(|(_, step)| step)
+            .filter(|step| matches!(step, Step::Decided(_)))
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (depth, step) in &self.steps {
+            writeln!(f, "{}- {}", "  ".repeat(*depth), step)?;
+        }
+        Ok(())
+    }
+}
+
+impl Packet {
+    /// Compares the same way as `cmp`, but records the walkthrough from the puzzle text
+    fn compare_explain(&self, other: &Packet) -> Explanation {
+        let mut steps = vec![];
+        let result = self.explain(other, 0, &mut steps);
+        Explanation { steps, result }
+    }
+
+    fn explain(&self, other: &Packet, depth: usize, steps: &mut Vec<(usize, Step)>) -> Ordering {
+        steps.push((depth, Step::Compare(self.clone(), other.clone())));
+        match (self, other) {
+            (Packet::Number(left), Packet::Number(right)) => {
+                let result = left.cmp(right);
+                match result {
+                    Ordering::Less => steps.push((depth + 1, Step::Decided(Decision::LeftSmaller))),
+                    Ordering::Greater => {
+                        steps.push((depth + 1, Step::Decided(Decision::RightSmaller)))
+                    }
+                    Ordering::Equal => {}
+                }
+                result
+            }
+            (Packet::List(left), Packet::List(right)) => {
+                for (l, r) in left.iter().zip(right.iter()) {
+                    let result = l.explain(r, depth + 1, steps);
+                    if result != Ordering::Equal {
+                        return result;
+                    }
+                }
+                let result = left.len().cmp(&right.len());
+                match result {
+                    Ordering::Less => steps.push((depth + 1, Step::Decided(Decision::LeftRanOut))),
+                    Ordering::Greater => {
+                        steps.push((depth + 1, Step::Decided(Decision::RightRanOut)))
+                    }
+                    Ordering::Equal => {}
+                }
+                result
+            }
+            (Packet::Number(_), Packet::List(_)) => {
+                let converted = Packet::List(vec![self.clone()]);
+                steps.push((depth + 1, Step::ConvertLeft(converted.clone())));
+                converted.explain(other, depth + 1, steps)
+            }
+            (Packet::List(_), Packet::Number(_)) => {
+                let converted = Packet::List(vec![other.clone()]);
+                steps.push((depth + 1, Step::ConvertRight(converted.clone())));
+                self.explain(&converted, depth + 1, steps)
+            }
+        }
+    }
+}
+
+/// A number or a list, with optional whitespace between tokens
+fn nom_packet(s: &str) -> IResult<&str, Packet> {
+    alt((map(i64, Packet::Number), nom_packet_list))(s)
+}
+
 fn nom_packet_list(s: &str) -> IResult<&str, Packet> {
-    let num_parser = map(i32, Packet::Number);
     map(
         delimited(
-            character::complete::char('['),
-            separated_list0(
-                character::complete::char(','),
-                alt((num_parser, nom_packet_list)),
-            ),
-            character::complete::char(']'),
+            pair(char('['), multispace0),
+            separated_list0(tuple((multispace0, char(','), multispace0)), nom_packet),
+            pair(multispace0, char(']')),
         ),
         Packet::List,
     )(s)
@@ -100,6 +242,22 @@ fn part1(input: &Input) -> Result<Output> {
     Ok(result)
 }
 
+/// Sums the pairs whose walkthrough ends by saying they are in the right order
+#[aoc(day13, part1, explained)]
+fn part1_explained(input: &Input) -> Result<Output> {
+    let mut result: i32 = 0;
+    for (idx, (left, right)) in input.iter().enumerate() {
+        let explanation = left.compare_explain(right);
+        if matches!(
+            explanation.deciding_step(),
+            Some(Step::Decided(Decision::LeftSmaller | Decision::LeftRanOut))
+        ) {
+            result += idx as i32 + 1;
+        }
+    }
+    Ok(result)
+}
+
 #[aoc(day13, part2)]
 fn part2(input: &Input) -> Result<Output> {
     let indicator1: Packet = "[[2]]".parse()?;
@@ -145,9 +303,57 @@ fn part2_unsorted(input: &Input) -> Result<Output> {
     Ok(head * (head + middle))
 }
 
+/// 1-based positions the `dividers` would have if sorted together with every packet in `reader`.
+/// Only a count per divider is kept, so the packets never need to be held in memory.
+/// Packets equal to a divider are placed after it.
+fn divider_ranks<R: BufRead>(reader: R, dividers: &[Packet]) -> Result<Vec<usize>> {
+    let order = (0..dividers.len())
+        .sorted_by(|a, b| dividers[*a].cmp(&dividers[*b]))
+        .collect_vec();
+    let sorted = order.iter().map(|idx| &dividers[*idx]).collect_vec();
+
+    // smaller[i] counts the packets that fall just below sorted[i]
+    let mut smaller = vec![0usize; sorted.len() + 1];
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let packet: Packet = line
+            .parse()
+            .with_context(|| format!("Line {}", line_no + 1))?;
+        smaller[sorted.partition_point(|d| **d <= packet)] += 1;
+    }
+
+    let mut ranks = vec![0; dividers.len()];
+    let mut below = 0;
+    for (position, idx) in order.iter().enumerate() {
+        below += smaller[position];
+        ranks[*idx] = below + position + 1;
+    }
+    Ok(ranks)
+}
+
+fn decoder_key<R: BufRead>(reader: R, dividers: &[Packet]) -> Result<usize> {
+    Ok(divider_ranks(reader, dividers)?.iter().product())
+}
+
+/// The streaming variant parses the packets itself, so only needs a shared handle on the raw text
+#[aoc_generator(day13, part2, streaming)]
+fn raw_generator(input: &Arc<str>) -> Arc<str> {
+    Arc::clone(input)
+}
+
+#[aoc(day13, part2, streaming)]
+fn part2_streaming(input: &Arc<str>) -> Result<Output> {
+    let dividers = ["[[2]]".parse()?, "[[6]]".parse()?];
+    Ok(decoder_key(input.as_bytes(), &dividers)? as Output)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::Lcg;
 
     const INPUT_STR: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
@@ -180,6 +386,7 @@ mod test {
             println!("{}\n{}\n\t{:?}\n", p.0, p.1, p.0.cmp(&p.1));
         }
         assert_eq!(part1(&input)?, 13);
+        assert_eq!(part1_explained(&input)?, 13);
         Ok(())
     }
 
@@ -188,6 +395,131 @@ mod test {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 140);
         assert_eq!(part2_unsorted(&input)?, 140);
+        assert_eq!(part2_streaming(&raw_generator(&INPUT_STR.into()))?, 140);
+        Ok(())
+    }
+
+    #[test]
+    fn parser_test() -> Result<()> {
+        let packet: Packet = " [ 1 ,[ ], [-2,\t9000000000] ]\n".parse()?;
+        assert_eq!(packet.to_string(), "[1,[],[-2,9000000000]]");
+
+        for (bad, offset) in [
+            ("[1,2]x", 5),
+            ("[1,2", 4),
+            ("[1,,2]", 2),
+            ("1", 0),
+            ("[1,[2]]]", 7),
+            ("[99999999999999999999]", 1),
+        ] {
+            let err = bad.parse::<Packet>().unwrap_err().to_string();
+            assert!(err.contains(&format!("at byte {}:", offset)), "{}", err);
+        }
+        Ok(())
+    }
+
+    fn random_packet(rng: &mut Lcg, depth: u32) -> Packet {
+        let r = rng.next_u31();
+        if depth > 4 || (depth > 0 && r % 3 == 1) {
+            Packet::Number(r as i64 - (1 << 30))
+        } else {
+            Packet::List((0..r % 5).map(|_| random_packet(rng, depth + 1)).collect())
+        }
+    }
+
+    #[test]
+    fn round_trip_test() -> Result<()> {
+        let mut rng = Lcg::new(13);
+        for _ in 0..500 {
+            let packet = random_packet(&mut rng, 0);
+            let text = packet.to_string();
+            assert_eq!(text.parse::<Packet>()?, packet);
+
+            let spaced = text
+                .replace('[', "[ ")
+                .replace(',', " ,\n")
+                .replace(']', "\t]");
+            assert_eq!(spaced.parse::<Packet>()?, packet);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn explain_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        for (left, right) in &input {
+            let explanation = left.compare_explain(right);
+            assert_eq!(explanation.result, left.cmp(right));
+            assert!(explanation.deciding_step().is_some());
+        }
+
+        assert_eq!(
+            input[1].0.compare_explain(&input[1].1).to_string(),
+            "- Compare [[1],[2,3,4]] vs [[1],4]
+  - Compare [1] vs [1]
+    - Compare 1 vs 1
+  - Compare [2,3,4] vs 4
+    - Mixed types; convert right to [4] and retry comparison
+    - Compare [2,3,4] vs [4]
+      - Compare 2 vs 4
+        - Left side is smaller, so inputs are in the right order
+"
+        );
+        assert_eq!(
+            input[2].0.compare_explain(&input[2].1).to_string(),
+            "- Compare [9] vs [[8,7,6]]
+  - Compare 9 vs [8,7,6]
+    - Mixed types; convert left to [9] and retry comparison
+    - Compare [9] vs [8,7,6]
+      - Compare 9 vs 8
+        - Right side is smaller, so inputs are not in the right order
+"
+        );
+        let explanation = input[3].0.compare_explain(&input[3].1);
+        assert_eq!(
+            explanation.deciding_step(),
+            Some(&Step::Decided(Decision::LeftRanOut))
+        );
+        assert_eq!(explanation.steps.last().map(|s| s.0), Some(1));
+
+        let packet = &input[0].0;
+        let explanation = packet.compare_explain(packet);
+        assert_eq!(explanation.result, Ordering::Equal);
+        assert_eq!(explanation.deciding_step(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_test() -> Result<()> {
+        let dividers: Vec<Packet> = vec!["[[2]]".parse()?, "[[6]]".parse()?];
+        assert_eq!(decoder_key(INPUT_STR.as_bytes(), &dividers)?, 140);
+
+        let input = input_generator(INPUT_STR)?;
+        let dividers: Vec<Packet> = ["[[6]]", "[]", "[[2]]", "[5]", "[10]", "[[6]]", "[1,1,3]"]
+            .iter()
+            .map(|d| d.parse())
+            .collect::<Result<_>>()?;
+        let sorted = input
+            .iter()
+            .flat_map(|p| [p.0.clone(), p.1.clone()])
+            .chain(dividers.iter().cloned())
+            .sorted()
+            .collect_vec();
+        let ranks = divider_ranks(INPUT_STR.as_bytes(), &dividers)?;
+        for (divider, rank) in dividers.iter().zip(ranks.iter()) {
+            assert_eq!(&sorted[rank - 1], divider);
+        }
+        assert!(ranks.iter().all_unique());
+
+        let path = std::env::temp_dir().join(format!("aoc_2022_day13_{}.txt", std::process::id()));
+        std::fs::write(&path, INPUT_STR)?;
+        let file = std::fs::File::open(&path)?;
+        // A buffer smaller than a packet makes the reader refill it part way through lines
+        let streamed = divider_ranks(std::io::BufReader::with_capacity(4, file), &dividers);
+        std::fs::remove_file(&path)?;
+        assert_eq!(streamed?, ranks);
+
+        assert!(divider_ranks("[1]\n[2\n".as_bytes(), &dividers).is_err());
         Ok(())
     }
 }
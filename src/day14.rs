@@ -1,22 +1,22 @@
-use std::collections::HashMap;
-
 use anyhow::{bail, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
-use colored::{Colorize, Color};
 use nom::{
     bytes::complete::tag,
-    character::{self, complete::i16},
+    character::{self, complete::i32},
     multi::separated_list1,
     sequence::separated_pair,
     IResult,
 };
 
-type Coord = (i16, i16);
+use crate::grid::{BoundingBox, Grid, Point, Point2};
+
+type Coord = Point2<i32>;
+const SOURCE: Coord = Point([500, 0]);
+
 #[derive(Debug, Clone)]
 struct Input {
-    map: HashMap<Coord, Obstacle>,
-    min: Coord,
-    max: Coord,
+    map: Grid<Obstacle>,
+    bounds: BoundingBox<i32, 2>,
     path: Vec<Coord>,
 }
 type Output = i16;
@@ -28,7 +28,8 @@ enum Obstacle {
 }
 
 fn parse_coord(s: &str) -> IResult<&str, Coord> {
-    separated_pair(i16, character::complete::char(','), i16)(s)
+    let (s, (x, y)) = separated_pair(i32, character::complete::char(','), i32)(s)?;
+    Ok((s, Point([x, y])))
 }
 
 fn parse_rock(s: &str) -> Result<Vec<Coord>> {
@@ -45,106 +46,66 @@ fn input_generator(input: &str) -> Result<Input> {
         .map(parse_rock)
         .collect::<Result<Vec<Vec<Coord>>>>()?;
 
-    let mut min = (i16::MAX, i16::MAX);
-    let mut max = (i16::MIN, i16::MIN);
-    let mut map = HashMap::new();
+    let mut bounds = BoundingBox::new(SOURCE);
+    let mut map = Grid::default();
     for r in rocks {
         let mut i = r.iter();
-        let curr = i.next().context("No first point")?;
-        min.0 = min.0.min(curr.0);
-        min.1 = min.1.min(curr.1);
-        max.0 = max.0.max(curr.0);
-        max.1 = max.1.max(curr.1);
-
-        let mut curr = *curr;
+        let mut curr = *i.next().context("No first point")?;
+        bounds.include(&curr);
         map.insert(curr, Obstacle::Rock);
         for next_point in i {
-            min.0 = min.0.min(next_point.0);
-            min.1 = min.1.min(next_point.1);
-            max.0 = max.0.max(next_point.0);
-            max.1 = max.1.max(next_point.1);
+            bounds.include(next_point);
+            let step = (*next_point - curr).signum();
             while curr != *next_point {
-                if curr.0 < next_point.0 {
-                    curr.0 += 1;
-                }
-                if curr.0 > next_point.0 {
-                    curr.0 -= 1;
-                }
-                if curr.1 < next_point.1 {
-                    curr.1 += 1;
-                }
-                if curr.1 > next_point.1 {
-                    curr.1 -= 1;
-                }
+                curr += step;
                 map.insert(curr, Obstacle::Rock);
             }
         }
     }
     Ok(Input {
         map,
-        min,
-        max,
-        path: vec![(500, 0)],
+        bounds,
+        path: vec![SOURCE],
     })
 }
 
-#[allow(dead_code)]
-fn print_map(input: &Input) {
-    for y in i16::min(input.min.1, 0)..input.max.1 + 2 {
-        for x in input.min.0 - 40..=input.max.0 + 40 {
-            let sym = if let Some(obs) = input.map.get(&(x, y)) {
-                match obs {
-                    Obstacle::Rock => "#".color(Color::White),
-                    Obstacle::Sand => "o".color(Color::Yellow),
-                }
-            } else {
-                " ".color(Color::Black)
-            };
-            print!("{}", sym);
-        }
-        println!()
-    }
-    println!()
+/// Where sand at `pos` moves next, or `None` if it has come to rest
+fn fall(map: &Grid<Obstacle>, pos: &Coord) -> Option<Coord> {
+    [Point([0, 1]), Point([-1, 1]), Point([1, 1])]
+        .into_iter()
+        .map(|d| *pos + d)
+        .find(|next| !map.contains(next))
 }
 
 fn drop_sand(input: &mut Input) -> bool {
-    let mut curr_pos = (500, 0);
-    while curr_pos.1 <= input.max.1 {
-        if !input.map.contains_key(&(curr_pos.0, curr_pos.1 + 1)) {
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 - 1, curr_pos.1 + 1)) {
-            curr_pos.0 -= 1;
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 + 1, curr_pos.1 + 1)) {
-            curr_pos.0 += 1;
-            curr_pos.1 += 1;
-        } else {
-            input.map.insert(curr_pos, Obstacle::Sand);
-            return false;
+    let mut curr_pos = SOURCE;
+    while curr_pos.y() <= input.bounds.max.y() {
+        match fall(&input.map, &curr_pos) {
+            Some(next) => curr_pos = next,
+            None => {
+                input.map.insert(curr_pos, Obstacle::Sand);
+                return false;
+            }
         }
     }
     true
 }
 
 fn drop_sand2(input: &mut Input) {
-    let mut curr_pos = (500, 0);
+    let mut curr_pos = SOURCE;
     loop {
         // println!("Current position: {:?}", curr_pos);
-        if curr_pos.1 == input.max.1 + 1 {
-            input.map.insert(curr_pos, Obstacle::Sand);
-            return;
-        } else if !input.map.contains_key(&(curr_pos.0, curr_pos.1 + 1)) {
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 - 1, curr_pos.1 + 1)) {
-            curr_pos.0 -= 1;
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 + 1, curr_pos.1 + 1)) {
-            curr_pos.0 += 1;
-            curr_pos.1 += 1;
-        } else {
+        if curr_pos.y() == input.bounds.max.y() + 1 {
             input.map.insert(curr_pos, Obstacle::Sand);
             return;
         }
+        match fall(&input.map, &curr_pos) {
+            Some(next) => curr_pos = next,
+            None => {
+                input.map.insert(curr_pos, Obstacle::Sand);
+                return;
+            }
+        }
     }
 }
 
@@ -152,7 +113,7 @@ fn drop_sand3(input: &mut Input) -> bool {
     let path = &mut input.path;
     let mut curr_pos = path.pop().unwrap();
     // head up
-    while input.map.contains_key(&curr_pos) {
+    while input.map.contains(&curr_pos) {
         curr_pos = path.pop().unwrap();
     }
 
@@ -161,20 +122,16 @@ fn drop_sand3(input: &mut Input) -> bool {
         path.push(curr_pos);
 
         // println!("Current position: {:?}", curr_pos);
-        if curr_pos.1 == input.max.1 + 1 {
+        if curr_pos.y() == input.bounds.max.y() + 1 {
             input.map.insert(curr_pos, Obstacle::Sand);
             return true;
-        } else if !input.map.contains_key(&(curr_pos.0, curr_pos.1 + 1)) {
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 - 1, curr_pos.1 + 1)) {
-            curr_pos.0 -= 1;
-            curr_pos.1 += 1;
-        } else if !input.map.contains_key(&(curr_pos.0 + 1, curr_pos.1 + 1)) {
-            curr_pos.0 += 1;
-            curr_pos.1 += 1;
-        } else {
-            input.map.insert(curr_pos, Obstacle::Sand);
-            return false;
+        }
+        match fall(&input.map, &curr_pos) {
+            Some(next) => curr_pos = next,
+            None => {
+                input.map.insert(curr_pos, Obstacle::Sand);
+                return false;
+            }
         }
     }
 }
@@ -204,7 +161,7 @@ fn part1_fast(input: &Input) -> Result<Output> {
 fn part2(input: &Input) -> Result<Output> {
     let mut input = input.clone();
     let mut steps = 0;
-    while !input.map.contains_key(&(500, 0)) {
+    while !input.map.contains(&SOURCE) {
         drop_sand2(&mut input);
         steps += 1;
     }
@@ -216,12 +173,10 @@ fn part2(input: &Input) -> Result<Output> {
 fn part2_fast(input: &Input) -> Result<Output> {
     let mut input = input.clone();
     let mut steps = 0;
-    while !input.map.contains_key(&(500, 0)) {
+    while !input.map.contains(&SOURCE) {
         drop_sand3(&mut input);
         steps += 1;
     }
-    // print_map(&input);
-
     Ok(steps)
 }
 
@@ -235,11 +190,6 @@ mod test {
     #[test]
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
-        // print_map(&input);
-        // while !drop_sand(&mut input) {
-        //     print_map(&input);
-        // }
-
         assert_eq!(part1(&input)?, 24);
         assert_eq!(part1_fast(&input)?, 24);
         Ok(())
@@ -248,14 +198,32 @@ mod test {
     #[test]
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
-        // for idx in 0..95 {
-        //     drop_sand2(&mut input);
-        //     println!("Cycle {}:", idx);
-        //     print_map(&input);
-        // }
-        // todo!();
         assert_eq!(part2(&input)?, 93);
         assert_eq!(part2_fast(&input)?, 93);
         Ok(())
     }
+
+    #[test]
+    fn render_test() -> Result<()> {
+        let mut input = input_generator(INPUT_STR)?;
+        while !drop_sand(&mut input) {}
+        let rendered = input.map.render(|o| match o {
+            Some(Obstacle::Rock) => '#',
+            Some(Obstacle::Sand) => 'o',
+            None => '.',
+        });
+        assert_eq!(
+            rendered,
+            "......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########.
+"
+        );
+        Ok(())
+    }
 }
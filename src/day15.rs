@@ -6,7 +6,9 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-type Coord = (i32, i32);
+use crate::grid::{Point, Point2};
+
+type Coord = Point2<i32>;
 type SimpleRange = (i32, i32);
 type Input = Vec<Sensor>;
 type Output = i64;
@@ -19,7 +21,7 @@ struct Sensor {
 
 impl Sensor {
     fn dist(&self, other: &Coord) -> i32 {
-        dist(&self.loc, other)
+        self.loc.manhattan(other)
     }
 
     fn max_dist(&self) -> i32 {
@@ -73,26 +75,22 @@ impl FromStr for Sensor {
         let m = RE.captures(s).context("Bad pattern")?;
         let x = m.get(1).context("No x")?.as_str();
         let y = m.get(2).context("No y")?.as_str();
-        let loc = (x.parse()?, y.parse()?);
+        let loc = Point([x.parse()?, y.parse()?]);
         let x = m.get(3).context("No x")?.as_str();
         let y = m.get(4).context("No y")?.as_str();
-        let beacon = (x.parse()?, y.parse()?);
+        let beacon = Point([x.parse()?, y.parse()?]);
 
         Ok(Self { loc, beacon })
     }
 }
 
-fn dist(a: &Coord, b: &Coord) -> i32 {
-    (a.0 - b.0).abs() + (a.1 - b.1).abs()
-}
-
 #[allow(dead_code)]
 fn print_sensor_chances(sensors: &[Sensor], coord: &Coord) {
     for (idx, s) in sensors.iter().enumerate() {
         let max_dist = s.max_dist();
         let dist = s.dist(coord);
         println!(
-            "{}\t{:?}:\t{}, ({}, {})",
+            "{}\t{}:\t{}, ({}, {})",
             idx,
             s.loc,
             dist <= max_dist,
@@ -108,15 +106,15 @@ fn no_beacons(sensors: &[Sensor], row: i32) -> (Vec<SimpleRange>, Vec<i32>) {
 
     for (_idx, s) in sensors.iter().enumerate() {
         let max_dist = s.max_dist();
-        let row_dist = s.dist(&(s.loc.0, row));
+        let row_dist = s.dist(&Point([s.loc.x(), row]));
         let diff = max_dist - row_dist;
         if diff >= 0 {
             // println!("{}\t{:?}:\t {}", idx, s.loc, diff);
-            ranges.push((s.loc.0 - diff, s.loc.0 + diff));
-            if s.loc.1 == row {
+            ranges.push((s.loc.x() - diff, s.loc.x() + diff));
+            if s.loc.y() == row {
                 in_use.insert(s.loc);
             }
-            if s.beacon.1 == row {
+            if s.beacon.y() == row {
                 in_use.insert(s.beacon);
             }
         }
@@ -135,7 +133,7 @@ fn no_beacons(sensors: &[Sensor], row: i32) -> (Vec<SimpleRange>, Vec<i32>) {
     // println!("Merged: {:?}", merged_ranges);
     // println!("In use: {:?}", in_use);
 
-    let in_use: Vec<i32> = in_use.iter().map(|c| c.0).sorted().collect_vec();
+    let in_use: Vec<i32> = in_use.iter().map(|c| c.x()).sorted().collect_vec();
 
     (merged_ranges, in_use)
 }
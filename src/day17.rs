@@ -7,7 +7,10 @@ use anyhow::{bail, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use strum::{EnumIter, IntoEnumIterator, EnumCount};
 
-type Coord = (i64, i64);
+use crate::grid::{Point, Point2};
+
+/// `y` grows upwards from the floor at 0
+type Coord = Point2<i64>;
 type Input = Vec<Direction>;
 type Output = i64;
 
@@ -30,44 +33,35 @@ impl Rock {
     fn new(rock_type: RockType, height: i64) -> Self {
         Self {
             rock_type,
-            location: (2, height),
+            location: Point([2, height]),
         }
     }
 
     // Returned order is the most likely to collide first
     fn locs(&self) -> Vec<Coord> {
-        let (x, y) = self.location;
-        match self.rock_type {
-            RockType::Flat => vec![(x, y), (x + 3, y), (x + 1, y), (x + 2, y)],
-            RockType::Tee => vec![
-                (x + 1, y),
-                (x, y + 1),
-                (x + 2, y + 1),
-                (x + 1, y + 1),
-                (x + 1, y + 2),
-            ],
-            RockType::Ell => vec![
-                (x, y),
-                (x + 2, y),
-                (x + 1, y),
-                (x + 2, y + 1),
-                (x + 2, y + 2),
-            ],
-            RockType::Pipe => vec![(x, y), (x, y + 1), (x, y + 2), (x, y + 3)],
-            RockType::Box => vec![(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)],
-        }
+        let offsets: &[(i64, i64)] = match self.rock_type {
+            RockType::Flat => &[(0, 0), (3, 0), (1, 0), (2, 0)],
+            RockType::Tee => &[(1, 0), (0, 1), (2, 1), (1, 1), (1, 2)],
+            RockType::Ell => &[(0, 0), (2, 0), (1, 0), (2, 1), (2, 2)],
+            RockType::Pipe => &[(0, 0), (0, 1), (0, 2), (0, 3)],
+            RockType::Box => &[(0, 0), (1, 0), (0, 1), (1, 1)],
+        };
+        offsets
+            .iter()
+            .map(|o| self.location + Point::from(*o))
+            .collect()
     }
 
     fn shift(&self, dir: Direction) -> Self {
-        let (x_off, y_off) = match dir {
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-            Direction::Down => (0, -1),
+        let offset = match dir {
+            Direction::Left => Point([-1, 0]),
+            Direction::Right => Point([1, 0]),
+            Direction::Down => Point([0, -1]),
         };
 
         Self {
             rock_type: self.rock_type,
-            location: (self.location.0 + x_off, self.location.1 + y_off),
+            location: self.location + offset,
         }
     }
 }
@@ -123,7 +117,7 @@ impl World {
     }
     fn can_insert(&self, rock: &Rock) -> bool {
         for coord in rock.locs() {
-            if coord.0 < 0 || coord.0 > 6 || coord.1 <= 0 {
+            if coord.x() < 0 || coord.x() > 6 || coord.y() <= 0 {
                 return false;
             }
             if self.coords.contains(&coord) {
@@ -135,7 +129,7 @@ impl World {
 
     fn insert(&mut self, rock: Rock) {
         for c in rock.locs() {
-            self.max_y = self.max_y.max(c.1);
+            self.max_y = self.max_y.max(c.y());
             self.coords.insert(c);
         }
         self.rock_count += 1;
@@ -151,9 +145,9 @@ impl World {
         for y in (1..=self.max_y + 7).rev() {
             print!("|");
             for x in 0..7 {
-                if self.coords.contains(&(x, y)) {
+                if self.coords.contains(&Point([x, y])) {
                     print!("#");
-                } else if rock.contains(&(x, y)) {
+                } else if rock.contains(&Point([x, y])) {
                     print!("@");
                 } else {
                     print!(".");
@@ -185,7 +179,7 @@ impl World {
                 rock = new_rock;
             } else {
                 self.insert(rock);
-                self.trim(rock.location.1);
+                self.trim(rock.location.y());
                 self.max_drop = self.max_drop.max(drop);
                 return;
             }
@@ -205,7 +199,7 @@ impl World {
 
         // println!("Trimming at {}", trim_height);
         let trim_height = height - 40;
-        self.coords.retain(|c| c.1 >= trim_height);
+        self.coords.retain(|c| c.y() >= trim_height);
         self.trim_height = trim_height;
     }
 
@@ -213,7 +207,7 @@ impl World {
     fn normalize(&self) -> Vec<Coord> {
         let mut result = vec![];
         for c in &self.coords {
-            result.push((c.0, self.max_y - c.1));
+            result.push(Point([c.x(), self.max_y - c.y()]));
         }
         result.sort();
         result
@@ -1,45 +1,316 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 use anyhow::{Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
+use strum::EnumString;
 
-type Coord = (i16, i16, i16);
+use crate::grid::{BoundingBox, Point, Point3};
+
+type Coord = Point3<i32>;
 struct Input {
     map: HashSet<Coord>,
-    max: Coord,
-    min: Coord,
+    bounds: BoundingBox<i32, 3>,
 }
 type Output = i32;
 
 #[aoc_generator(day18)]
 fn input_generator(input: &str) -> Result<Input> {
     let mut result = HashSet::new();
-    let mut max_a = i16::MIN;
-    let mut max_b = i16::MIN;
-    let mut max_c = i16::MIN;
-    let mut min_a = i16::MAX;
-    let mut min_b = i16::MAX;
-    let mut min_c = i16::MAX;
     for l in input.lines() {
         let mut parts = l.split(',');
         let a = parts.next().context("No part")?.parse()?;
         let b = parts.next().context("No part")?.parse()?;
         let c = parts.next().context("No part")?.parse()?;
-        max_a = max_a.max(a);
-        min_a = min_a.min(a);
-        max_b = max_b.max(b);
-        min_b = min_b.min(b);
-        max_c = max_c.max(c);
-        min_c = min_c.min(c);
-        result.insert((a, b, c));
+        result.insert(Point([a, b, c]));
     }
+    let bounds = BoundingBox::from_points(&result).unwrap_or(BoundingBox::new(Point::default()));
     Ok(Input {
         map: result,
-        max: (max_a, max_b, max_c),
-        min: (min_a, min_b, min_c),
+        bounds,
     })
 }
 
+/// The bounding box grown by one in each direction, so that the outside air surrounds the droplet
+fn air_bounds(input: &Input) -> BoundingBox<i32, 3> {
+    BoundingBox {
+        min: input.bounds.min - Point([1, 1, 1]),
+        max: input.bounds.max + Point([1, 1, 1]),
+    }
+}
+
+const FACES: [Coord; 6] = [
+    Point([-1, 0, 0]),
+    Point([1, 0, 0]),
+    Point([0, -1, 0]),
+    Point([0, 1, 0]),
+    Point([0, 0, -1]),
+    Point([0, 0, 1]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connectivity {
+    /// Air cells are connected only through shared faces (6 neighbours)
+    Face,
+    /// Air cells are connected through shared faces, edges, and corners (26 neighbours)
+    Vertex,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> Vec<Coord> {
+        match self {
+            Connectivity::Face => Point::default().orthogonal().collect(),
+            Connectivity::Vertex => Point::default().adjacent().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AirPocket {
+    /// Number of air cells in the pocket.
+    /// For the exterior, this only counts cells within one step of the bounding box.
+    volume: usize,
+    /// Number of lava faces touching the pocket
+    surface: usize,
+    exterior: bool,
+}
+
+#[derive(Debug, Clone)]
+struct VoxelAnalysis {
+    labels: HashMap<Coord, usize>,
+    pockets: Vec<AirPocket>,
+}
+
+impl VoxelAnalysis {
+    /// Labels every air cell in the bounding box (grown by one in each direction) with the index of its pocket.
+    /// Pocket 0 always starts from a corner of the grown box and so is the exterior.
+    fn new(input: &Input, connectivity: Connectivity) -> Self {
+        let map = &input.map;
+        let mut labels = HashMap::new();
+        let mut pockets = vec![];
+        if map.is_empty() {
+            return Self { labels, pockets };
+        }
+
+        let bounds = air_bounds(input);
+        let (min, max) = (bounds.min, bounds.max);
+        let offsets = connectivity.offsets();
+
+        for x in min.x()..=max.x() {
+            for y in min.y()..=max.y() {
+                for z in min.z()..=max.z() {
+                    let start = Point([x, y, z]);
+                    if map.contains(&start) || labels.contains_key(&start) {
+                        continue;
+                    }
+                    let label = pockets.len();
+                    let mut pocket = AirPocket {
+                        volume: 0,
+                        surface: 0,
+                        exterior: label == 0,
+                    };
+                    labels.insert(start, label);
+                    let mut queue = vec![start];
+                    while let Some(c) = queue.pop() {
+                        pocket.volume += 1;
+                        pocket.surface += FACES.iter().filter(|d| map.contains(&(c + **d))).count();
+                        for d in &offsets {
+                            let next = c + *d;
+                            if bounds.contains(&next)
+                                && !map.contains(&next)
+                                && !labels.contains_key(&next)
+                            {
+                                labels.insert(next, label);
+                                queue.push(next);
+                            }
+                        }
+                    }
+                    pockets.push(pocket);
+                }
+            }
+        }
+
+        Self { labels, pockets }
+    }
+
+    fn exterior(&self) -> Option<&AirPocket> {
+        self.pockets.first()
+    }
+
+    fn cavities(&self) -> impl Iterator<Item = &AirPocket> {
+        self.pockets.iter().filter(|p| !p.exterior)
+    }
+
+    /// Returns the pocket containing `c`, or `None` for lava and cells outside of the analysed box.
+    fn pocket(&self, c: &Coord) -> Option<&AirPocket> {
+        self.labels.get(c).map(|label| &self.pockets[*label])
+    }
+}
+
+/// Corners of each face of the unit cube, matching the order of `FACES`.
+/// They are counter-clockwise when viewed from outside so that face normals point out of the droplet.
+const FACE_CORNERS: [[[i32; 3]; 4]; 6] = [
+    [[0, 0, 0], [0, 0, 1], [0, 1, 1], [0, 1, 0]],
+    [[1, 0, 0], [1, 1, 0], [1, 1, 1], [1, 0, 1]],
+    [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]],
+    [[0, 1, 0], [0, 1, 1], [1, 1, 1], [1, 1, 0]],
+    [[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 0, 0]],
+    [[0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]],
+];
+
+/// One line per enclosed cavity giving its volume and surface.
+/// With `diagonal` set, air also flows through the edges and corners of cubes.
+pub fn describe_cavities(input: &str, diagonal: bool) -> Result<String> {
+    let input = input_generator(input)?;
+    let connectivity = if diagonal {
+        Connectivity::Vertex
+    } else {
+        Connectivity::Face
+    };
+    let analysis = VoxelAnalysis::new(&input, connectivity);
+    Ok(analysis
+        .cavities()
+        .enumerate()
+        .map(|(idx, p)| {
+            format!(
+                "Cavity {}: volume {}, surface {}\n",
+                idx + 1,
+                p.volume,
+                p.surface
+            )
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FaceSelection {
+    /// Every face not touching another cube, including those facing internal cavities
+    All,
+    /// Only faces reachable from outside the droplet
+    Exterior,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MeshFace {
+    /// Indices into `Mesh::vertices`
+    corners: [usize; 4],
+    normal: Coord,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Mesh {
+    vertices: Vec<Coord>,
+    faces: Vec<MeshFace>,
+}
+
+impl Mesh {
+    fn new(input: &Input, selection: FaceSelection) -> Self {
+        let map = &input.map;
+        let analysis = match selection {
+            FaceSelection::All => None,
+            FaceSelection::Exterior => Some(VoxelAnalysis::new(input, Connectivity::Face)),
+        };
+
+        let mut result = Mesh::default();
+        let mut vertex_ids: HashMap<Coord, usize> = HashMap::new();
+        for cube in map.iter().sorted() {
+            for (normal, corners) in FACES.iter().zip(FACE_CORNERS.iter()) {
+                let neighbour = *cube + *normal;
+                if map.contains(&neighbour) {
+                    continue;
+                }
+                if let Some(analysis) = &analysis {
+                    if !analysis.pocket(&neighbour).is_some_and(|p| p.exterior) {
+                        continue;
+                    }
+                }
+                let corners = corners.map(|corner| {
+                    let vertex = *cube + Point(corner);
+                    *vertex_ids.entry(vertex).or_insert_with(|| {
+                        result.vertices.push(vertex);
+                        result.vertices.len() - 1
+                    })
+                });
+                result.faces.push(MeshFace {
+                    corners,
+                    normal: *normal,
+                });
+            }
+        }
+        result
+    }
+
+    /// Wavefront OBJ with one quad per face
+    fn to_obj(&self) -> String {
+        let mut result = String::new();
+        for v in &self.vertices {
+            result += &format!("v {} {} {}\n", v.x(), v.y(), v.z());
+        }
+        for n in FACES {
+            result += &format!("vn {} {} {}\n", n.x(), n.y(), n.z());
+        }
+        for face in &self.faces {
+            let normal_id = FACES
+                .iter()
+                .position(|n| *n == face.normal)
+                .unwrap_or_default()
+                + 1;
+            result += "f";
+            for c in face.corners {
+                result += &format!(" {}//{}", c + 1, normal_id);
+            }
+            result += "\n";
+        }
+        result
+    }
+
+    /// ASCII STL with each face split into two triangles
+    fn to_stl(&self, name: &str) -> String {
+        let mut result = format!("solid {}\n", name);
+        for face in &self.faces {
+            let [a, b, c, d] = face.corners;
+            for triangle in [[a, b, c], [a, c, d]] {
+                let n = face.normal;
+                result += &format!("  facet normal {} {} {}\n", n.x(), n.y(), n.z());
+                result += "    outer loop\n";
+                for v in triangle.map(|idx| self.vertices[idx]) {
+                    result += &format!("      vertex {} {} {}\n", v.x(), v.y(), v.z());
+                }
+                result += "    endloop\n";
+                result += "  endfacet\n";
+            }
+        }
+        result += &format!("endsolid {}\n", name);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum MeshFormat {
+    Obj,
+    Stl,
+}
+
+/// Writes the droplet described by `input` to `path` so that it can be loaded into a 3D viewer
+pub fn write_mesh(input: &str, path: &Path, exterior_only: bool, format: MeshFormat) -> Result<()> {
+    let input = input_generator(input)?;
+    let selection = if exterior_only {
+        FaceSelection::Exterior
+    } else {
+        FaceSelection::All
+    };
+    let mesh = Mesh::new(&input, selection);
+    let text = match format {
+        MeshFormat::Obj => mesh.to_obj(),
+        MeshFormat::Stl => mesh.to_stl("droplet"),
+    };
+    std::fs::write(path, text).with_context(|| format!("Could not write {}", path.display()))
+}
+
 #[aoc(day18, part1)]
 fn part1(input: &Input) -> Result<Output> {
     // println!("Min: {:?}", input.min);
@@ -47,24 +318,7 @@ fn part1(input: &Input) -> Result<Output> {
     let map = &input.map;
     let mut border = map.len() * 6;
     for c in map {
-        if map.contains(&(c.0 - 1, c.1, c.2)) {
-            border -= 1;
-        }
-        if map.contains(&(c.0 + 1, c.1, c.2)) {
-            border -= 1;
-        }
-        if map.contains(&(c.0, c.1 - 1, c.2)) {
-            border -= 1;
-        }
-        if map.contains(&(c.0, c.1 + 1, c.2)) {
-            border -= 1;
-        }
-        if map.contains(&(c.0, c.1, c.2 - 1)) {
-            border -= 1;
-        }
-        if map.contains(&(c.0, c.1, c.2 + 1)) {
-            border -= 1;
-        }
+        border -= c.orthogonal().filter(|n| map.contains(n)).count();
     }
 
     Ok(border as i32)
@@ -72,13 +326,12 @@ fn part1(input: &Input) -> Result<Output> {
 
 #[aoc(day18, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let (min_x, min_y, min_z) = (input.min.0 - 1, input.min.1 - 1, input.min.2 - 1);
-    let (max_x, max_y, max_z) = (input.max.0 + 1, input.max.1 + 1, input.max.2 + 1);
+    let bounds = air_bounds(input);
     let map = &input.map;
 
     let mut visited = HashSet::new();
 
-    let mut queue = vec![(min_x, min_y, min_z)];
+    let mut queue = vec![bounds.min];
     let mut border = 0;
 
     while let Some(c) = queue.pop() {
@@ -86,52 +339,23 @@ fn part2(input: &Input) -> Result<Output> {
             continue;
         }
         visited.insert(c);
-        if c.0 > min_x {
-            if map.contains(&(c.0 - 1, c.1, c.2)) {
-                border += 1;
-            } else if !visited.contains(&(c.0 - 1, c.1, c.2)) {
-                queue.push((c.0 - 1, c.1, c.2));
-            }
-        }
-        if c.1 > min_y {
-            if map.contains(&(c.0, c.1 - 1, c.2)) {
-                border += 1;
-            } else if !visited.contains(&(c.0, c.1 - 1, c.2)) {
-                queue.push((c.0, c.1 - 1, c.2));
-            }
-        }
-        if c.2 > min_z {
-            if map.contains(&(c.0, c.1, c.2 - 1)) {
-                border += 1;
-            } else if !visited.contains(&(c.0, c.1, c.2 - 1)) {
-                queue.push((c.0, c.1, c.2 - 1));
-            }
-        }
-        if c.0 < max_x {
-            if map.contains(&(c.0 + 1, c.1, c.2)) {
-                border += 1;
-            } else if !visited.contains(&(c.0 + 1, c.1, c.2)) {
-                queue.push((c.0 + 1, c.1, c.2));
-            }
-        }
-        if c.1 < max_y {
-            if map.contains(&(c.0, c.1 + 1, c.2)) {
-                border += 1;
-            } else if !visited.contains(&(c.0, c.1 + 1, c.2)) {
-                queue.push((c.0, c.1 + 1, c.2));
-            }
-        }
-        if c.2 < max_z {
-            if map.contains(&(c.0, c.1, c.2 + 1)) {
+        for n in c.orthogonal().filter(|n| bounds.contains(n)) {
+            if map.contains(&n) {
                 border += 1;
-            } else if !visited.contains(&(c.0, c.1, c.2 + 1)) {
-                queue.push((c.0, c.1, c.2 + 1));
+            } else if !visited.contains(&n) {
+                queue.push(n);
             }
         }
     }
     Ok(border)
 }
 
+#[aoc(day18, part2, components)]
+fn part2_components(input: &Input) -> Result<Output> {
+    let analysis = VoxelAnalysis::new(input, Connectivity::Face);
+    Ok(analysis.exterior().map(|p| p.surface).unwrap_or_default() as i32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,6 +386,78 @@ mod tests {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 58);
+        assert_eq!(part2_components(&input)?, 58);
+        Ok(())
+    }
+
+    #[test]
+    fn voxel_analysis_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        let analysis = VoxelAnalysis::new(&input, Connectivity::Face);
+        let cavities = analysis.cavities().collect::<Vec<_>>();
+        assert_eq!(cavities.len(), 1);
+        assert_eq!(cavities[0].volume, 1);
+        assert_eq!(cavities[0].surface, 6);
+        assert_eq!(analysis.pocket(&Point([2, 2, 5])), Some(cavities[0]));
+        assert_eq!(analysis.pocket(&Point([2, 2, 2])), None);
+        let total: usize = analysis.pockets.iter().map(|p| p.surface).sum();
+        assert_eq!(total as i32, part1(&input)?);
+
+        // The trapped cell touches the outside through its edges
+        let analysis = VoxelAnalysis::new(&input, Connectivity::Vertex);
+        assert_eq!(analysis.cavities().count(), 0);
+        assert_eq!(analysis.exterior().map(|p| p.surface), Some(64));
+        assert_eq!(
+            describe_cavities(INPUT_STR, false)?,
+            "Cavity 1: volume 1, surface 6\n"
+        );
+        assert_eq!(describe_cavities(INPUT_STR, true)?, "");
+
+        // Same droplet, shifted to negative coordinates
+        let shifted = INPUT_STR
+            .lines()
+            .map(|l| {
+                l.split(',')
+                    .map(|v| v.parse::<i32>().unwrap() - 10)
+                    .join(",")
+            })
+            .join("\n");
+        let input = input_generator(&shifted)?;
+        let analysis = VoxelAnalysis::new(&input, Connectivity::Face);
+        assert_eq!(analysis.cavities().count(), 1);
+        assert_eq!(analysis.exterior().map(|p| p.surface), Some(58));
+        Ok(())
+    }
+
+    #[test]
+    fn mesh_test() -> Result<()> {
+        let cube = input_generator("0,0,0")?;
+        let mesh = Mesh::new(&cube, FaceSelection::All);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.faces.len(), 6);
+        assert_eq!(
+            mesh.to_obj()
+                .lines()
+                .filter(|l| l.starts_with("f "))
+                .count(),
+            6
+        );
+        let stl = mesh.to_stl("cube");
+        assert!(stl.starts_with("solid cube\n"));
+        assert!(stl.ends_with("endsolid cube\n"));
+        assert_eq!(stl.matches("facet normal").count(), 12);
+
+        let input = input_generator(INPUT_STR)?;
+        assert_eq!(Mesh::new(&input, FaceSelection::All).faces.len(), 64);
+        let exterior = Mesh::new(&input, FaceSelection::Exterior);
+        assert_eq!(exterior.faces.len(), 58);
+        assert!(exterior.vertices.iter().all_unique());
+
+        let path = std::env::temp_dir().join(format!("aoc_2022_day18_{}.stl", std::process::id()));
+        write_mesh(INPUT_STR, &path, true, "stl".parse()?)?;
+        let written = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(written.matches("facet normal").count(), 2 * 58);
         Ok(())
     }
 }
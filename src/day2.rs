@@ -1,35 +1,219 @@
-use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+use anyhow::{ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 
-type Input = Vec<(Move, Move)>;
+type Input = Vec<(char, char)>;
 type Output = i32;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct OutcomeScores {
+    lose: i32,
+    draw: i32,
+    win: i32,
+}
+
+impl Default for OutcomeScores {
+    fn default() -> Self {
+        Self {
+            lose: 0,
+            draw: 3,
+            win: 6,
+        }
+    }
+}
+
+/// A cyclic game with an odd number of moves, where each move beats the `(size - 1) / 2` moves before it.
+/// Rock-paper-scissors is `[Rock, Paper, Scissors]`
+/// and rock-paper-scissors-lizard-Spock is `[Rock, Spock, Paper, Lizard, Scissors]`.
+/// Moves are numbered from 0 and are worth one more than their number.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-enum Move {
-    Rock,
-    Paper,
-    Scissors,
+struct Game {
+    size: usize,
+    scores: OutcomeScores,
 }
 
-fn move_value(action: Move) -> i32 {
-    match action {
-        Move::Rock => 1,
-        Move::Paper => 2,
-        Move::Scissors => 3,
+impl Game {
+    fn new(size: usize, scores: OutcomeScores) -> Result<Self> {
+        ensure!(size % 2 == 1, "Cyclic games need an odd number of moves");
+        Ok(Self { size, scores })
+    }
+
+    fn outcome(&self, me: usize, other: usize) -> Outcome {
+        let diff = (me + self.size - other) % self.size;
+        if diff == 0 {
+            Outcome::Draw
+        } else if diff <= self.size / 2 {
+            Outcome::Win
+        } else {
+            Outcome::Lose
+        }
+    }
+
+    /// A move which gets `outcome` against `other`.
+    /// When several moves would do, this picks the one next to `other` in the cycle.
+    fn move_for(&self, other: usize, outcome: Outcome) -> usize {
+        match outcome {
+            Outcome::Lose => (other + self.size - 1) % self.size,
+            Outcome::Draw => other,
+            Outcome::Win => (other + 1) % self.size,
+        }
+    }
+
+    fn score(&self, me: usize, other: usize) -> i32 {
+        let outcome = match self.outcome(me, other) {
+            Outcome::Lose => self.scores.lose,
+            Outcome::Draw => self.scores.draw,
+            Outcome::Win => self.scores.win,
+        };
+        me as i32 + 1 + outcome
     }
 }
 
-fn result_score(me: Move, other: Move) -> i32 {
-    if me == other {
-        3
-    } else if (me == Move::Rock && other == Move::Scissors)
-        || (me == Move::Paper && other == Move::Rock)
-        || (me == Move::Scissors && other == Move::Paper)
-    {
-        6
+/// What the second column of the strategy guide means
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Decoder {
+    /// The move I should play
+    Move(HashMap<char, usize>),
+    /// The outcome I should aim for
+    Outcome(HashMap<char, Outcome>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Strategy {
+    game: Game,
+    opponent: HashMap<char, usize>,
+    decoder: Decoder,
+}
+
+impl Strategy {
+    fn rock_paper_scissors(decoder: Decoder) -> Result<Self> {
+        Ok(Self {
+            game: Game::new(3, OutcomeScores::default())?,
+            opponent: HashMap::from([('A', 0), ('B', 1), ('C', 2)]),
+            decoder,
+        })
+    }
+
+    fn my_move(&self, other: usize, letter: char) -> Result<usize> {
+        let me = match &self.decoder {
+            Decoder::Move(moves) => *moves.get(&letter).context("Unexpected value")?,
+            Decoder::Outcome(outcomes) => {
+                let outcome = outcomes.get(&letter).context("Unexpected value")?;
+                self.game.move_for(other, *outcome)
+            }
+        };
+        ensure!(me < self.game.size, "Move {} is not part of the game", me);
+        Ok(me)
+    }
+
+    fn play(&self, guide: &Input) -> Result<i32> {
+        let mut result = 0;
+        for (opponent, me) in guide {
+            let other = *self.opponent.get(opponent).context("Unexpected value")?;
+            ensure!(
+                other < self.game.size,
+                "Move {} is not part of the game",
+                other
+            );
+            result += self.game.score(self.my_move(other, *me)?, other);
+        }
+        Ok(result)
+    }
+}
+
+const OUTCOMES: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Goal {
+    Maximise,
+    Minimise,
+}
+
+/// Every way of reading `letters` as distinct moves of `game`, or as distinct outcomes.
+fn all_decoders(game: &Game, letters: &[char]) -> Vec<Decoder> {
+    let moves = (0..game.size)
+        .permutations(letters.len())
+        .map(|p| Decoder::Move(letters.iter().copied().zip(p).collect()));
+    let outcomes = OUTCOMES
+        .iter()
+        .copied()
+        .permutations(letters.len())
+        .map(|p| Decoder::Outcome(letters.iter().copied().zip(p).collect()));
+    moves.chain(outcomes).collect()
+}
+
+/// The score of the guide under each decoder, in the order given
+fn decoder_scores(
+    strategy: &Strategy,
+    decoders: Vec<Decoder>,
+    guide: &Input,
+) -> Result<Vec<(Decoder, i32)>> {
+    decoders
+        .into_iter()
+        .map(|decoder| {
+            let strategy = Strategy {
+                decoder,
+                ..strategy.clone()
+            };
+            let score = strategy.play(guide)?;
+            Ok((strategy.decoder, score))
+        })
+        .collect()
+}
+
+/// The index of the decoder that best meets `goal`.
+/// Ties go to the first decoder, so move readings win over outcome readings.
+fn best_decoder(scores: &[(Decoder, i32)], goal: Goal) -> Option<usize> {
+    let scores = scores.iter().map(|(_, score)| *score).enumerate();
+    let best = match goal {
+        Goal::Maximise => scores.rev().max_by_key(|(_, score)| *score),
+        Goal::Minimise => scores.min_by_key(|(_, score)| *score),
+    };
+    best.map(|(idx, _)| idx)
+}
+
+/// Lists the score of the guide in `input` under every bijection of X/Y/Z to moves and to outcomes.
+/// The decoder giving the highest score, or the lowest if `worst` is set, is marked with a `*`.
+pub fn search_decoders(input: &str, worst: bool) -> Result<String> {
+    const MOVES: [&str; 3] = ["Rock", "Paper", "Scissors"];
+    let guide = input_generator(input)?;
+    let goal = if worst {
+        Goal::Minimise
     } else {
-        0
+        Goal::Maximise
+    };
+    let strategy = Strategy::rock_paper_scissors(Decoder::Move(HashMap::new()))?;
+    let decoders = all_decoders(&strategy.game, &['X', 'Y', 'Z']);
+    let scores = decoder_scores(&strategy, decoders, &guide)?;
+    let best = best_decoder(&scores, goal).context("No decoders")?;
+
+    let mut result = String::new();
+    for (idx, (decoder, score)) in scores.iter().enumerate() {
+        let meanings = match decoder {
+            Decoder::Move(moves) => moves
+                .iter()
+                .sorted()
+                .map(|(letter, me)| format!("{} = {}", letter, MOVES[*me]))
+                .join(", "),
+            Decoder::Outcome(outcomes) => outcomes
+                .iter()
+                .sorted_by_key(|(letter, _)| **letter)
+                .map(|(letter, outcome)| format!("{} = {:?}", letter, outcome))
+                .join(", "),
+        };
+        let marker = if idx == best { '*' } else { ' ' };
+        result.push_str(&format!("{} {}: {}\n", marker, meanings, score));
     }
+    Ok(result)
 }
 
 #[aoc_generator(day2)]
@@ -39,56 +223,32 @@ fn input_generator(input: &str) -> Result<Input> {
         let mut parts = l.split_whitespace();
         let opponent = parts.next().context("No opponent move")?;
         let me = parts.next().context("No my move")?;
-
-        let opponent = match opponent {
-            "A" => Move::Rock,
-            "B" => Move::Paper,
-            "C" => Move::Scissors,
-            _ => bail!("Unexpected value"),
-        };
-        let me = match me {
-            "X" => Move::Rock,
-            "Y" => Move::Paper,
-            "Z" => Move::Scissors,
-            _ => bail!("Unexpected value"),
-        };
-        result.push((opponent, me));
+        ensure!(
+            opponent.chars().count() == 1 && me.chars().count() == 1,
+            "Unexpected value"
+        );
+        result.push((
+            opponent.chars().next().context("No opponent move")?,
+            me.chars().next().context("No my move")?,
+        ));
     }
     Ok(result)
 }
 
 #[aoc(day2, part1)]
 fn part1(input: &Input) -> Result<Output> {
-    let mut result = 0;
-    for game in input {
-        // print!("{:?}", game);
-        result += result_score(game.1, game.0) + move_value(game.1);
-    }
-    Ok(result)
+    let decoder = Decoder::Move(HashMap::from([('X', 0), ('Y', 1), ('Z', 2)]));
+    Strategy::rock_paper_scissors(decoder)?.play(input)
 }
 
 #[aoc(day2, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let mut result = 0;
-    for game in input {
-        let other = game.0;
-        let me = match game.1 {
-            Move::Rock => match other {
-                Move::Rock => Move::Scissors,
-                Move::Paper => Move::Rock,
-                Move::Scissors => Move::Paper,
-            },
-            Move::Paper => other,
-            Move::Scissors => match other {
-                Move::Rock => Move::Paper,
-                Move::Paper => Move::Scissors,
-                Move::Scissors => Move::Rock,
-            },
-        };
-        result += result_score(me, other) + move_value(me);
-        // println!("{:?} {:?} {}", other, me, result);
-    }
-    Ok(result)
+    let decoder = Decoder::Outcome(HashMap::from([
+        ('X', Outcome::Lose),
+        ('Y', Outcome::Draw),
+        ('Z', Outcome::Win),
+    ]));
+    Strategy::rock_paper_scissors(decoder)?.play(input)
 }
 
 #[cfg(test)]
@@ -110,4 +270,91 @@ mod test {
         assert_eq!(part2(&input)?, 12);
         Ok(())
     }
+
+    #[test]
+    fn spock_test() -> Result<()> {
+        // Rock, Spock, Paper, Lizard, Scissors
+        let game = Game::new(5, OutcomeScores::default())?;
+        for me in 0..5 {
+            let wins = (0..5)
+                .filter(|other| game.outcome(me, *other) == Outcome::Win)
+                .count();
+            assert_eq!(wins, 2);
+            for outcome in [Outcome::Lose, Outcome::Draw, Outcome::Win] {
+                assert_eq!(game.outcome(game.move_for(me, outcome), me), outcome);
+            }
+        }
+        // Rock crushes Scissors, Scissors cuts Paper, Spock vaporizes Rock
+        assert_eq!(game.outcome(0, 4), Outcome::Win);
+        assert_eq!(game.outcome(4, 2), Outcome::Win);
+        assert_eq!(game.outcome(0, 1), Outcome::Lose);
+        assert!(Game::new(4, OutcomeScores::default()).is_err());
+
+        let strategy = Strategy {
+            game: Game::new(
+                5,
+                OutcomeScores {
+                    lose: -1,
+                    draw: 0,
+                    win: 1,
+                },
+            )?,
+            opponent: "ABCDE".chars().zip(0..5).collect(),
+            decoder: Decoder::Move("VWXYZ".chars().zip(0..5).collect()),
+        };
+        let input = input_generator("E V\nA A\nB Z")?;
+        assert!(strategy.play(&input).is_err());
+        // Rock beats Scissors, Rock draws with Rock, Scissors loses to Spock
+        let input = input_generator("E V\nA V\nB Z")?;
+        assert_eq!(strategy.play(&input)?, 2 + 1 + 4);
+        Ok(())
+    }
+
+    #[test]
+    fn decoder_search_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        let strategy = Strategy::rock_paper_scissors(Decoder::Move(HashMap::new()))?;
+        let decoders = all_decoders(&strategy.game, &['X', 'Y', 'Z']);
+        assert_eq!(decoders.len(), 12);
+        let scores = decoder_scores(&strategy, decoders, &input)?;
+        assert_eq!(scores[0].1, 15);
+        assert!(scores.iter().any(|(_, score)| *score == 12));
+
+        // A Paper vs Rock, B Scissors vs Paper, C Rock vs Scissors
+        let best = best_decoder(&scores, Goal::Maximise).context("No decoders")?;
+        assert_eq!(scores[best].1, (2 + 6) + (3 + 6) + (1 + 6));
+        assert_eq!(
+            scores[best].0,
+            Decoder::Move(HashMap::from([('X', 2), ('Y', 1), ('Z', 0)]))
+        );
+        // A Scissors loses to Rock, B Rock to Paper, C Paper to Scissors
+        let worst = best_decoder(&scores, Goal::Minimise).context("No decoders")?;
+        assert_eq!(scores[worst].1, 3 + 1 + 2);
+        assert_eq!(
+            scores[worst].0,
+            Decoder::Move(HashMap::from([('X', 0), ('Y', 2), ('Z', 1)]))
+        );
+        assert_eq!(best_decoder(&[], Goal::Maximise), None);
+
+        assert_eq!(
+            search_decoders(INPUT_STR, false)?,
+            "  X = Rock, Y = Paper, Z = Scissors: 15
+  X = Rock, Y = Scissors, Z = Paper: 6
+  X = Paper, Y = Rock, Z = Scissors: 15
+  X = Paper, Y = Scissors, Z = Rock: 15
+  X = Scissors, Y = Rock, Z = Paper: 15
+* X = Scissors, Y = Paper, Z = Rock: 24
+  X = Lose, Y = Draw, Z = Win: 12
+  X = Lose, Y = Win, Z = Draw: 15
+  X = Draw, Y = Lose, Z = Win: 15
+  X = Draw, Y = Win, Z = Lose: 15
+  X = Win, Y = Lose, Z = Draw: 18
+  X = Win, Y = Draw, Z = Lose: 15
+"
+        );
+        let worst = search_decoders(INPUT_STR, true)?;
+        let marked = worst.lines().filter(|l| l.starts_with('*')).collect_vec();
+        assert_eq!(marked, vec!["* X = Rock, Y = Scissors, Z = Paper: 6"]);
+        Ok(())
+    }
 }
@@ -4,7 +4,9 @@ use anyhow::{bail, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use num_integer::Roots;
 
-type Coord = (i32, i32);
+use crate::grid::{Direction, Grid, Point, Point2};
+
+type Coord = Point2<i32>;
 struct Input {
     map: Map,
     start: Coord,
@@ -12,7 +14,7 @@ struct Input {
 }
 
 struct Map {
-    tiles: HashMap<Coord, Tile>,
+    tiles: Grid<Tile>,
     row_limits: HashMap<i32, RangeInclusive<i32>>,
     col_limits: HashMap<i32, RangeInclusive<i32>>,
     cube_edge: i32,
@@ -33,15 +35,15 @@ impl TryFrom<&Map> for CubeMap {
         let real = cube_edge == 50;
         let mut sides = vec![];
         sides.resize_with(6 + 1, HashMap::new);
-        for (coord, tile) in &map.tiles {
-            let side = get_side(*coord, map);
-            let coord = (coord.0 % cube_edge, coord.1 % cube_edge);
+        for (coord, tile) in map.tiles.iter() {
+            let side = get_side(coord, map);
+            let coord = Point([coord.x() % cube_edge, coord.y() % cube_edge]);
             let coord = if real {
                 match side {
                     1 => coord,
-                    2 => (cube_edge - coord.1 - 1, coord.0),
-                    3 => (cube_edge - coord.1 - 1, coord.0),
-                    4 => (cube_edge - coord.1 - 1, coord.0),
+                    2 => Point([cube_edge - coord.y() - 1, coord.x()]),
+                    3 => Point([cube_edge - coord.y() - 1, coord.x()]),
+                    4 => Point([cube_edge - coord.y() - 1, coord.x()]),
                     5 => coord,
                     6 => coord,
                     _ => bail!("Unsupported side"),
@@ -51,7 +53,7 @@ impl TryFrom<&Map> for CubeMap {
                     1 => coord,
                     2 => coord,
                     3 => coord,
-                    4 => (coord.1, cube_edge - coord.0 - 1),
+                    4 => Point([coord.y(), cube_edge - coord.x() - 1]),
                     5 => coord,
                     6 => coord, // Top to left
                     _ => bail!("Unsupported side"),
@@ -72,12 +74,7 @@ struct Walker {
 
 impl Walker {
     fn step(&self) -> Coord {
-        match self.direction {
-            Direction::Right => (self.loc.0 + 1, self.loc.1),
-            Direction::Down => (self.loc.0, self.loc.1 + 1),
-            Direction::Left => (self.loc.0 - 1, self.loc.1),
-            Direction::Up => (self.loc.0, self.loc.1 - 1),
-        }
+        self.direction.step(&self.loc)
     }
 
     fn score(&self) -> i32 {
@@ -87,18 +84,10 @@ impl Walker {
             Direction::Left => 2,
             Direction::Up => 3,
         };
-        1000 * (self.loc.1 + 1) + 4 * (self.loc.0 + 1) + dir_value
+        1000 * (self.loc.y() + 1) + 4 * (self.loc.x() + 1) + dir_value
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Direction {
-    Right,
-    Down,
-    Left,
-    Up,
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
 enum Tile {
     Empty,
@@ -136,54 +125,38 @@ impl TryFrom<char> for Tile {
     }
 }
 
-impl Direction {
-    fn rotate(&self, sym: &str) -> Result<Direction> {
-        let result = match sym {
-            "R" => match self {
-                Direction::Right => Direction::Down,
-                Direction::Down => Direction::Left,
-                Direction::Left => Direction::Up,
-                Direction::Up => Direction::Right,
-            },
-            "L" => match self {
-                Direction::Right => Direction::Up,
-                Direction::Down => Direction::Right,
-                Direction::Left => Direction::Down,
-                Direction::Up => Direction::Left,
-            },
-            _ => bail!("Invalid rotation: {}", sym),
-        };
+fn rotate(direction: Direction, sym: &str) -> Result<Direction> {
+    let result = match sym {
+        "R" => direction.turn_right(),
+        "L" => direction.turn_left(),
+        _ => bail!("Invalid rotation: {}", sym),
+    };
 
-        Ok(result)
-    }
+    Ok(result)
 }
 
 #[aoc_generator(day22)]
 fn input_generator(input: &str) -> Result<Input> {
     let (unparsed_map, unparsed_instructions) = input.split_once("\n\n").context("Bad format")?;
     // println!("Instructions {}", unparsed_instructions);
-    let mut map = HashMap::new();
     let mut row_limits = HashMap::new();
     let mut col_limits = HashMap::new();
     let mut start = None;
-    // let mut width = None;
-    for (y, line) in unparsed_map.lines().enumerate() {
-        let y = y as i32;
-        for (x, tile_char) in line.chars().enumerate() {
-            let x = x as i32;
-            let tile: Tile = tile_char.try_into()?;
-            if tile == Tile::Empty && start.is_none() {
-                start = Some((x, y));
-            }
-            if tile != Tile::OffGrid {
-                let limit = row_limits.entry(y).or_insert_with(|| (x..=x));
-                *limit = *limit.start().min(&x)..=*limit.end().max(&x);
-                let limit = col_limits.entry(x).or_insert_with(|| (y..=y));
-                *limit = *limit.start().min(&y)..=*limit.end().max(&y);
-                map.insert((x, y), tile);
-            }
+    let map = Grid::parse_sparse(unparsed_map, |coord, tile_char| {
+        let (x, y) = (coord.x(), coord.y());
+        let tile: Tile = tile_char.try_into()?;
+        if tile == Tile::Empty && start.is_none() {
+            start = Some(coord);
         }
-    }
+        if tile == Tile::OffGrid {
+            return Ok(None);
+        }
+        let limit = row_limits.entry(y).or_insert_with(|| (x..=x));
+        *limit = *limit.start().min(&x)..=*limit.end().max(&x);
+        let limit = col_limits.entry(x).or_insert_with(|| (y..=y));
+        *limit = *limit.start().min(&y)..=*limit.end().max(&y);
+        Ok(Some(tile))
+    })?;
 
     let cube_edge = (map.len() / 6).sqrt() as i32;
     let map = Map {
@@ -218,24 +191,10 @@ fn input_generator(input: &str) -> Result<Input> {
     })
 }
 
-#[allow(dead_code)]
-fn print_map(map: &Map) {
-    let y_range = map.col_limits.values().fold(0..=0, |a, b| {
-        *a.start().min(b.start())..=*a.end().max(b.end())
-    });
-    for y in y_range {
-        for x in 0..=*map.row_limits.get(&y).unwrap().end() {
-            print!("{}", map.tiles.get(&(x, y)).unwrap_or(&Tile::OffGrid));
-        }
-
-        println!();
-    }
-}
-
 fn handle_instruction(me: &mut Walker, input: &Input, instruction: &str) -> Result<()> {
     let map = &input.map;
     let tiles = &map.tiles;
-    if let Ok(new_direction) = me.direction.rotate(instruction) {
+    if let Ok(new_direction) = rotate(me.direction, instruction) {
         me.direction = new_direction;
         return Ok(());
     } else {
@@ -249,13 +208,13 @@ fn handle_instruction(me: &mut Walker, input: &Input, instruction: &str) -> Resu
                 me.loc = new_me;
             } else if tile == Tile::OffGrid {
                 // println!("Wrapping: {:?}", new_me);
-                let row_limit = map.row_limits.get(&new_me.1).unwrap_or(&(0..=0));
-                let col_limit = map.col_limits.get(&new_me.0).unwrap_or(&(0..=0));
+                let row_limit = map.row_limits.get(&new_me.y()).unwrap_or(&(0..=0));
+                let col_limit = map.col_limits.get(&new_me.x()).unwrap_or(&(0..=0));
                 new_me = match me.direction {
-                    Direction::Right => (*row_limit.start(), new_me.1),
-                    Direction::Down => (new_me.0, *col_limit.start()),
-                    Direction::Left => (*row_limit.end(), new_me.1),
-                    Direction::Up => (new_me.0, *col_limit.end()),
+                    Direction::Right => Point([*row_limit.start(), new_me.y()]),
+                    Direction::Down => Point([new_me.x(), *col_limit.start()]),
+                    Direction::Left => Point([*row_limit.end(), new_me.y()]),
+                    Direction::Up => Point([new_me.x(), *col_limit.end()]),
                 };
                 if tiles.get(&new_me).unwrap_or_default() == &Tile::Empty {
                     me.loc = new_me;
@@ -269,8 +228,8 @@ fn handle_instruction(me: &mut Walker, input: &Input, instruction: &str) -> Resu
 }
 
 fn get_side(loc: Coord, map: &Map) -> usize {
-    let row = loc.1 / map.cube_edge;
-    let col = loc.0 / map.cube_edge;
+    let row = loc.y() / map.cube_edge;
+    let col = loc.x() / map.cube_edge;
     if map.cube_edge == 4 {
         // Test case
         match (row, col) {
@@ -306,7 +265,7 @@ fn handle_instruction_cube(
 ) -> Result<()> {
     let sides = &map.sides;
     let cube_edge = map.cube_edge;
-    if let Ok(new_direction) = me.direction.rotate(instruction) {
+    if let Ok(new_direction) = rotate(me.direction, instruction) {
         me.direction = new_direction;
         return Ok(());
     } else {
@@ -333,22 +292,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 4;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (cube_edge - me.loc.1 - 1, 0);
+                            new_me.loc = Point([cube_edge - me.loc.y() - 1, 0]);
                         }
                         Direction::Down => {
                             new_me.side = 5;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (me.loc.0, 0);
+                            new_me.loc = Point([me.loc.x(), 0]);
                         }
                         Direction::Left => {
                             new_me.side = 3;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (me.loc.1, 0);
+                            new_me.loc = Point([me.loc.y(), 0]);
                         }
                         Direction::Up => {
                             new_me.side = 2;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (cube_edge - me.loc.0 - 1, 0);
+                            new_me.loc = Point([cube_edge - me.loc.x() - 1, 0]);
                         }
                     }
                 } else if me.side == 2 {
@@ -356,22 +315,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 3;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, me.loc.1);
+                            new_me.loc = Point([0, me.loc.y()]);
                         }
                         Direction::Down => {
                             new_me.side = 6;
                             new_me.direction = Direction::Up;
-                            new_me.loc = (cube_edge - me.loc.0 - 1, cube_edge - 1);
+                            new_me.loc = Point([cube_edge - me.loc.x() - 1, cube_edge - 1]);
                         }
                         Direction::Left => {
                             new_me.side = 4;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, me.loc.1);
+                            new_me.loc = Point([cube_edge - 1, me.loc.y()]);
                         }
                         Direction::Up => {
                             new_me.side = 1;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (cube_edge - me.loc.0 - 1, 0);
+                            new_me.loc = Point([cube_edge - me.loc.x() - 1, 0]);
                         }
                     }
                 } else if me.side == 3 {
@@ -379,22 +338,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 5;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, me.loc.1);
+                            new_me.loc = Point([0, me.loc.y()]);
                         }
                         Direction::Down => {
                             new_me.side = 6;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, cube_edge - me.loc.0 - 1);
+                            new_me.loc = Point([0, cube_edge - me.loc.x() - 1]);
                         }
                         Direction::Left => {
                             new_me.side = 2;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, me.loc.1);
+                            new_me.loc = Point([cube_edge - 1, me.loc.y()]);
                         }
                         Direction::Up => {
                             new_me.side = 1;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, me.loc.0);
+                            new_me.loc = Point([0, me.loc.x()]);
                         }
                     }
                 } else if me.side == 4 {
@@ -402,22 +361,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 2;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, me.loc.1);
+                            new_me.loc = Point([0, me.loc.y()]);
                         }
                         Direction::Down => {
                             new_me.side = 6;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, me.loc.0);
+                            new_me.loc = Point([cube_edge - 1, me.loc.x()]);
                         }
                         Direction::Left => {
                             new_me.side = 5;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, me.loc.1);
+                            new_me.loc = Point([cube_edge - 1, me.loc.y()]);
                         }
                         Direction::Up => {
                             new_me.side = 1;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, cube_edge - me.loc.0 - 1);
+                            new_me.loc = Point([cube_edge - 1, cube_edge - me.loc.x() - 1]);
                         }
                     }
                 } else if me.side == 5 {
@@ -425,22 +384,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 4;
                             new_me.direction = Direction::Right;
-                            new_me.loc = (0, me.loc.1);
+                            new_me.loc = Point([0, me.loc.y()]);
                         }
                         Direction::Down => {
                             new_me.side = 6;
                             new_me.direction = Direction::Down;
-                            new_me.loc = (me.loc.0, 0);
+                            new_me.loc = Point([me.loc.x(), 0]);
                         }
                         Direction::Left => {
                             new_me.side = 3;
                             new_me.direction = Direction::Left;
-                            new_me.loc = (cube_edge - 1, me.loc.1);
+                            new_me.loc = Point([cube_edge - 1, me.loc.y()]);
                         }
                         Direction::Up => {
                             new_me.side = 1;
                             new_me.direction = Direction::Up;
-                            new_me.loc = (me.loc.0, cube_edge - 1);
+                            new_me.loc = Point([me.loc.x(), cube_edge - 1]);
                         }
                     }
                 } else if me.side == 6 {
@@ -448,22 +407,22 @@ fn handle_instruction_cube(
                         Direction::Right => {
                             new_me.side = 4;
                             new_me.direction = Direction::Up;
-                            new_me.loc = (me.loc.1, cube_edge - 1);
+                            new_me.loc = Point([me.loc.y(), cube_edge - 1]);
                         }
                         Direction::Down => {
                             new_me.side = 2; // TODO
                             new_me.direction = Direction::Up;
-                            new_me.loc = (cube_edge - me.loc.0 - 1, cube_edge - 1);
+                            new_me.loc = Point([cube_edge - me.loc.x() - 1, cube_edge - 1]);
                         }
                         Direction::Left => {
                             new_me.side = 3;
                             new_me.direction = Direction::Up;
-                            new_me.loc = (cube_edge - me.loc.1 - 1, cube_edge - 1);
+                            new_me.loc = Point([cube_edge - me.loc.y() - 1, cube_edge - 1]);
                         }
                         Direction::Up => {
                             new_me.side = 5;
                             new_me.direction = Direction::Up;
-                            new_me.loc = (me.loc.0, cube_edge - 1);
+                            new_me.loc = Point([me.loc.x(), cube_edge - 1]);
                         }
                     }
                 } else {
@@ -505,7 +464,7 @@ fn part1(input: &Input) -> Result<Output> {
 #[aoc(day22, part2)]
 fn part2(input: &Input) -> Result<Output> {
     let mut me = Walker {
-        loc: (0, 0),
+        loc: Point([0, 0]),
         direction: Direction::Right,
         side: 1,
     };
@@ -534,28 +493,43 @@ fn flatten_walker(walker: &Walker, map: &CubeMap) -> Result<Walker> {
     let direction = walker.direction;
     let (loc, direction) = if real {
         match walker.side {
-            1 => ((loc.0 + cube_edge, loc.1), direction),
-            2 => ((loc.1, 4 * cube_edge - loc.0 - 1), direction.rotate("L")?),
-            3 => ((loc.1, 3 * cube_edge - loc.0 - 1), direction.rotate("L")?),
+            1 => (Point([loc.x() + cube_edge, loc.y()]), direction),
+            2 => (
+                Point([loc.y(), 4 * cube_edge - loc.x() - 1]),
+                direction.turn_left(),
+            ),
+            3 => (
+                Point([loc.y(), 3 * cube_edge - loc.x() - 1]),
+                direction.turn_left(),
+            ),
             4 => (
-                (2 * cube_edge + loc.1, cube_edge - loc.0 - 1),
-                direction.rotate("L")?,
+                Point([2 * cube_edge + loc.y(), cube_edge - loc.x() - 1]),
+                direction.turn_left(),
+            ),
+            5 => (Point([loc.x() + cube_edge, loc.y() + cube_edge]), direction),
+            6 => (
+                Point([loc.x() + cube_edge, loc.y() + 2 * cube_edge]),
+                direction,
             ),
-            5 => ((loc.0 + cube_edge, loc.1 + cube_edge), direction),
-            6 => ((loc.0 + cube_edge, loc.1 + 2 * cube_edge), direction),
             _ => bail!("Unsupported side: {}", walker.side),
         }
     } else {
         match walker.side {
-            1 => ((loc.0 + 2 * cube_edge, loc.1), direction),
-            2 => ((loc.0, loc.1 + cube_edge), direction),
-            3 => ((loc.0 + cube_edge, loc.1 + cube_edge), direction),
+            1 => (Point([loc.x() + 2 * cube_edge, loc.y()]), direction),
+            2 => (Point([loc.x(), loc.y() + cube_edge]), direction),
+            3 => (Point([loc.x() + cube_edge, loc.y() + cube_edge]), direction),
             4 => (
-                (4 * cube_edge - loc.1 - 1, 2 * cube_edge + loc.0),
-                walker.direction.rotate("R")?,
+                Point([4 * cube_edge - loc.y() - 1, 2 * cube_edge + loc.x()]),
+                walker.direction.turn_right(),
+            ),
+            5 => (
+                Point([loc.x() + 2 * cube_edge, loc.y() + cube_edge]),
+                direction,
+            ),
+            6 => (
+                Point([loc.x() + 2 * cube_edge, loc.y() + 2 * cube_edge]),
+                direction,
             ),
-            5 => ((loc.0 + 2 * cube_edge, loc.1 + cube_edge), direction),
-            6 => ((loc.0 + 2 * cube_edge, loc.1 + 2 * cube_edge), direction),
             _ => bail!("Unsupported side: {}", walker.side),
         }
     };
@@ -568,6 +542,8 @@ fn flatten_walker(walker: &Walker, map: &CubeMap) -> Result<Walker> {
 
 #[cfg(test)]
 mod test {
+    use itertools::Itertools;
+
     use super::*;
 
     const INPUT_STR: &str = "        ...#     
@@ -588,8 +564,12 @@ mod test {
     #[test]
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
-        print_map(&input.map);
-        println!("{:?}", input.start);
+        let rendered = input.map.tiles.render(|t| *t.unwrap_or_default());
+        assert_eq!(
+            rendered.lines().map(str::trim_end).collect_vec(),
+            INPUT_STR.lines().take(12).map(str::trim_end).collect_vec()
+        );
+        assert_eq!(input.start, Point([8, 0]));
         assert_eq!(part1(&input)?, 6032);
         Ok(())
     }
@@ -1,11 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
-use anyhow::{Result, Context};
+use anyhow::{ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use strum::{EnumIter, IntoEnumIterator};
 
-type Coord = (i32, i32);
-type Input = HashSet<Coord>;
+use crate::grid::{Compass, Grid, Point, Point2};
+
+type Coord = Point2<i32>;
+type Input = Grid<()>;
 type Output = i32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
@@ -17,106 +19,120 @@ enum Direction {
 }
 
 impl Direction {
+    /// The three cells an elf checks before proposing a move, with the move itself in the middle
     fn checks(&self, loc: &Coord) -> [Coord; 3] {
-        match self {
-            Direction::North => [
-                (loc.0 - 1, loc.1 - 1),
-                (loc.0, loc.1 - 1),
-                (loc.0 + 1, loc.1 - 1),
-            ],
-            Direction::South => [
-                (loc.0 - 1, loc.1 + 1),
-                (loc.0, loc.1 + 1),
-                (loc.0 + 1, loc.1 + 1),
-            ],
-            Direction::West => [
-                (loc.0 - 1, loc.1 - 1),
-                (loc.0 - 1, loc.1),
-                (loc.0 - 1, loc.1 + 1),
-            ],
-            Direction::East => [
-                (loc.0 + 1, loc.1 - 1),
-                (loc.0 + 1, loc.1),
-                (loc.0 + 1, loc.1 + 1),
-            ],
-        }
+        let compass = match self {
+            Direction::North => [Compass::NW, Compass::N, Compass::NE],
+            Direction::South => [Compass::SW, Compass::S, Compass::SE],
+            Direction::West => [Compass::NW, Compass::W, Compass::SW],
+            Direction::East => [Compass::NE, Compass::E, Compass::SE],
+        };
+        compass.map(|c| c.step(loc))
     }
 
     fn propose(&self, loc: &Coord) -> Coord {
-        match self {
-            Direction::North => (loc.0, loc.1 - 1),
-            Direction::South => (loc.0, loc.1 + 1),
-            Direction::East => (loc.0 + 1, loc.1),
-            Direction::West => (loc.0 - 1, loc.1),
-        }
+        self.checks(loc)[1]
     }
 }
+
 #[aoc_generator(day23)]
 fn input_generator(input: &str) -> Result<Input> {
-    let mut result = HashSet::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            if c == '#' {
-                result.insert((x as i32, y as i32));
-            }
-        }
-    }
+    Grid::parse_sparse(input, |_, c| Ok((c == '#').then_some(())))
+}
 
-    Ok(result)
+fn render_map(map: &Input) -> String {
+    map.render(|elf| if elf.is_some() { '#' } else { '.' })
+}
+
+/// Which neighbours an elf looks at before deciding whether it needs to move at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+enum Neighbourhood {
+    /// All eight surrounding cells
+    Moore,
+    /// Only the four orthogonal cells
+    VonNeumann,
 }
 
-fn bounding_box(map: &Input) -> (Coord, Coord) {
-    let mut min = (i32::MAX, i32::MAX);
-    let mut max = (i32::MIN, i32::MIN);
-    for c in map {
-        min.0 = min.0.min(c.0);
-        min.1 = min.1.min(c.1);
-        max.0 = max.0.max(c.0);
-        max.1 = max.1.max(c.1);
+impl Neighbourhood {
+    fn is_crowded(&self, map: &Input, elf: &Coord) -> bool {
+        match self {
+            Neighbourhood::Moore => Compass::ALL.iter().any(|c| map.contains(&c.step(elf))),
+            Neighbourhood::VonNeumann => Direction::iter().any(|d| map.contains(&d.propose(elf))),
+        }
     }
-    (min, max)
 }
 
+/// What happens when several elves propose the same destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
-fn print_map(map: &Input) {
-    let (min, max) = bounding_box(map);
-    for y in min.1..=max.1 {
-        for x in min.0..=max.0 {
-            if map.contains(&(x, y)) {
-                print!("#");
-            } else {
-                print!(".");
-            }
+enum ConflictRule {
+    /// Nobody moves to a contested cell
+    AllStay,
+    /// The first claimant in reading order (top to bottom, left to right) moves, the rest stay
+    FirstWins,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rules {
+    /// Proposal order for the first round. It is rotated by one for each following round.
+    order: Vec<Direction>,
+    neighbourhood: Neighbourhood,
+    conflict: ConflictRule,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            order: Direction::iter().collect(),
+            neighbourhood: Neighbourhood::Moore,
+            conflict: ConflictRule::AllStay,
         }
-        println!();
     }
-    println!();
 }
 
-fn adjacent(coord: &Coord) -> [Coord; 8] {
-    [(coord.0 - 1, coord.1 - 1), (coord.0, coord.1 - 1), (coord.0 + 1, coord.1 - 1),
-    (coord.0 - 1, coord.1), /*                 */ (coord.0 + 1, coord.1),
-    (coord.0 - 1, coord.1 + 1), (coord.0, coord.1 + 1), (coord.0 + 1, coord.1 + 1)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+enum RunLength {
+    /// Stop after the first round in which no elf moves
+    UntilStable,
+    Rounds(usize),
 }
 
-fn round<I>(map: &mut Input, directions: &I) -> Result<i32>
-where
-    I: Iterator<Item = Direction> + Clone,
-{
+#[derive(Debug, Clone)]
+struct Simulation {
+    map: Input,
+    /// Rounds actually run, including the final one without movement when running until stable
+    rounds: usize,
+    stable: bool,
+    /// Text snapshots of the initial state and the end of every round, if requested
+    frames: Vec<String>,
+}
+
+/// Runs a single round (zero-based `round_idx`) and returns how many elves moved
+fn round(map: &mut Input, rules: &Rules, round_idx: usize) -> Result<i32> {
+    ensure!(!rules.order.is_empty(), "No proposal directions");
+    let directions = rules
+        .order
+        .iter()
+        .cycle()
+        .skip(round_idx % rules.order.len())
+        .take(rules.order.len());
+
     let mut result = 0;
-    let mut proposals: HashMap<(i32, i32), i32> = HashMap::new();
-    let mut plans = HashMap::new();
+    let mut proposals: HashMap<Coord, i32> = HashMap::new();
+    let mut plans = vec![];
 
-    for elf in map.iter() {
-        if !adjacent(elf).iter().any(|elf| map.contains(elf)) {
+    for (elf, _) in map.iter() {
+        if !rules.neighbourhood.is_crowded(map, &elf) {
             continue;
         }
-        for d in directions.clone().take(4) {
-            let valid = d.checks(elf).iter().all(|elf| !map.contains(elf));
+        for d in directions.clone() {
+            let valid = d.checks(&elf).iter().all(|elf| !map.contains(elf));
             if valid {
                 // println!("{:?} {:?}", elf, d);
-                plans.insert(*elf, d.propose(elf));
-                *proposals.entry(d.propose(elf)).or_default() += 1;
+                plans.push((elf, d.propose(&elf)));
+                *proposals.entry(d.propose(&elf)).or_default() += 1;
                 break;
             }
         }
@@ -125,49 +141,340 @@ where
 
     // println!("proposals {:?}", proposals);
 
-    for (elf, plan) in plans.iter() {
-        if proposals.get(plan).context("Missing proposal count")? == &1 {
-            map.remove(elf);
-            map.insert(*plan);
-            result += 1;
+    match rules.conflict {
+        ConflictRule::AllStay => {
+            for (elf, plan) in plans.iter() {
+                if proposals.get(plan).context("Missing proposal count")? == &1 {
+                    map.remove(elf);
+                    map.insert(*plan, ());
+                    result += 1;
+                }
+            }
+        }
+        ConflictRule::FirstWins => {
+            // Every destination was empty at the start of the round, so moving one at a time is safe
+            plans.sort_unstable_by_key(|(elf, _)| (elf.y(), elf.x()));
+            let mut claimed = HashSet::new();
+            for (elf, plan) in plans.iter() {
+                if claimed.insert(*plan) {
+                    map.remove(elf);
+                    map.insert(*plan, ());
+                    result += 1;
+                }
+            }
         }
     }
 
     Ok(result)
 }
 
+fn simulate(
+    input: &Input,
+    rules: &Rules,
+    length: RunLength,
+    record_frames: bool,
+) -> Result<Simulation> {
+    let mut sim = Simulation {
+        map: input.clone(),
+        rounds: 0,
+        stable: false,
+        frames: vec![],
+    };
+    if record_frames {
+        sim.frames
+            .push(format!("== Initial State ==\n{}", render_map(&sim.map)));
+    }
+
+    while length != RunLength::Rounds(sim.rounds) {
+        let moved = round(&mut sim.map, rules, sim.rounds)?;
+        sim.rounds += 1;
+        if record_frames {
+            sim.frames.push(format!(
+                "== End of Round {} ==\n{}",
+                sim.rounds,
+                render_map(&sim.map)
+            ));
+        }
+        if moved == 0 {
+            sim.stable = true;
+            if length == RunLength::UntilStable {
+                break;
+            }
+        }
+    }
+    Ok(sim)
+}
+
+fn shift_east(row: &[u64]) -> Vec<u64> {
+    let mut carry = 0;
+    row.iter()
+        .map(|w| {
+            let result = (w << 1) | carry;
+            carry = w >> 63;
+            result
+        })
+        .collect()
+}
+
+fn shift_west(row: &[u64]) -> Vec<u64> {
+    let mut carry = 0;
+    let mut result = row
+        .iter()
+        .rev()
+        .map(|w| {
+            let result = (w >> 1) | carry;
+            carry = w << 63;
+            result
+        })
+        .collect::<Vec<_>>();
+    result.reverse();
+    result
+}
+
+fn combine<F>(a: &[u64], b: &[u64], f: F) -> Vec<u64>
+where
+    F: Fn(u64, u64) -> u64,
+{
+    a.iter().zip(b.iter()).map(|(a, b)| f(*a, *b)).collect()
+}
+
+/// Dense bit-packed elf map.
+/// Bit `x % 64` of `rows[y][x / 64]` is set when there is an elf at `origin + (x, y)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitGrid {
+    rows: Vec<Vec<u64>>,
+    words: usize,
+    origin: Coord,
+}
+
+impl BitGrid {
+    fn new(map: &Input) -> Self {
+        if map.is_empty() {
+            return Self {
+                rows: vec![],
+                words: 1,
+                origin: Point::default(),
+            };
+        }
+        let bounds = map.bounding_box().expect("Non-empty map");
+        let size = bounds.size();
+        let words = size.x() as usize / 64 + 1;
+        let mut rows = vec![vec![0u64; words]; size.y() as usize];
+        for (elf, _) in map.iter() {
+            let offset = elf - bounds.min;
+            let x = offset.x() as usize;
+            rows[offset.y() as usize][x / 64] |= 1 << (x % 64);
+        }
+        Self {
+            rows,
+            words,
+            origin: bounds.min,
+        }
+    }
+
+    fn to_map(&self) -> Input {
+        let mut result = Grid::default();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (idx, word) in row.iter().enumerate() {
+                for bit in (0..64).filter(|bit| word & (1 << bit) != 0) {
+                    result.insert(self.origin + Point([(idx * 64 + bit) as i32, y as i32]), ());
+                }
+            }
+        }
+        result
+    }
+
+    /// Makes sure that the outermost rows and columns are empty so no elf can move off the grid
+    fn grow(&mut self) {
+        if self.rows.first().is_some_and(|r| r.iter().any(|w| *w != 0)) {
+            self.rows.insert(0, vec![0; self.words]);
+            self.origin[1] -= 1;
+        }
+        if self.rows.last().is_some_and(|r| r.iter().any(|w| *w != 0)) {
+            self.rows.push(vec![0; self.words]);
+        }
+        if self.rows.iter().any(|r| r[0] & 1 != 0) {
+            for r in self.rows.iter_mut() {
+                r.insert(0, 0);
+            }
+            self.words += 1;
+            self.origin[0] -= 64;
+        }
+        if self.rows.iter().any(|r| r[self.words - 1] >> 63 != 0) {
+            for r in self.rows.iter_mut() {
+                r.push(0);
+            }
+            self.words += 1;
+        }
+    }
+
+    /// Same as `round` but computes the checks for a whole row at a time.
+    /// Only opposite proposals can ever collide, so conflicts are resolved by comparing rows two apart
+    /// (north/south) or columns two apart (east/west).
+    fn round(&mut self, rules: &Rules, round_idx: usize) -> Result<i32> {
+        ensure!(!rules.order.is_empty(), "No proposal directions");
+        ensure!(
+            rules.conflict == ConflictRule::AllStay,
+            "Unsupported conflict rule {:?}",
+            rules.conflict
+        );
+        self.grow();
+
+        let empty = vec![0u64; self.words];
+        let height = self.rows.len();
+        let rows = &self.rows;
+        let row = |y: usize| rows.get(y).unwrap_or(&empty);
+        let directions = rules
+            .order
+            .iter()
+            .cycle()
+            .skip(round_idx % rules.order.len())
+            .take(rules.order.len());
+
+        // Indexed by `Direction as usize`
+        let mut proposals: Vec<[Vec<u64>; 4]> = Vec::with_capacity(height);
+        for y in 0..height {
+            let (n, c, s) = (row(y.wrapping_sub(1)), row(y), row(y + 1));
+            let n3 = combine(
+                &combine(n, &shift_east(n), |a, b| a | b),
+                &shift_west(n),
+                |a, b| a | b,
+            );
+            let s3 = combine(
+                &combine(s, &shift_east(s), |a, b| a | b),
+                &shift_west(s),
+                |a, b| a | b,
+            );
+            let cols = combine(&combine(n, c, |a, b| a | b), s, |a, b| a | b);
+            let w3 = shift_east(&cols);
+            let e3 = shift_west(&cols);
+            let crowded = match rules.neighbourhood {
+                Neighbourhood::Moore => combine(
+                    &combine(&n3, &s3, |a, b| a | b),
+                    &combine(&w3, &e3, |a, b| a | b),
+                    |a, b| a | b,
+                ),
+                Neighbourhood::VonNeumann => combine(
+                    &combine(n, s, |a, b| a | b),
+                    &combine(&shift_east(c), &shift_west(c), |a, b| a | b),
+                    |a, b| a | b,
+                ),
+            };
+
+            let mut remaining = combine(c, &crowded, |a, b| a & b);
+            let mut row_proposals: [Vec<u64>; 4] = std::array::from_fn(|_| empty.clone());
+            for d in directions.clone() {
+                let blocked = match d {
+                    Direction::North => &n3,
+                    Direction::South => &s3,
+                    Direction::West => &w3,
+                    Direction::East => &e3,
+                };
+                let proposal = combine(&remaining, blocked, |a, b| a & !b);
+                remaining = combine(&remaining, &proposal, |a, b| a & !b);
+                row_proposals[*d as usize] = proposal;
+            }
+            proposals.push(row_proposals);
+        }
+
+        let proposal = |y: usize, d: Direction| {
+            proposals
+                .get(y)
+                .map_or(&empty, |p: &[Vec<u64>; 4]| &p[d as usize])
+        };
+        let mut moved: Vec<[Vec<u64>; 4]> = Vec::with_capacity(height);
+        for y in 0..height {
+            let north = proposal(y, Direction::North);
+            let south = proposal(y, Direction::South);
+            let west = proposal(y, Direction::West);
+            let east = proposal(y, Direction::East);
+            moved.push([
+                combine(
+                    north,
+                    proposal(y.wrapping_sub(2), Direction::South),
+                    |a, b| a & !b,
+                ),
+                combine(south, proposal(y + 2, Direction::North), |a, b| a & !b),
+                combine(west, &shift_east(&shift_east(east)), |a, b| a & !b),
+                combine(east, &shift_west(&shift_west(west)), |a, b| a & !b),
+            ]);
+        }
+
+        let moved_row = |y: usize, d: Direction| moved.get(y).map_or(&empty, |m| &m[d as usize]);
+        let mut new_rows = Vec::with_capacity(height);
+        let mut result = 0;
+        for y in 0..height {
+            let mut new_row = row(y).clone();
+            for d in Direction::iter() {
+                new_row = combine(&new_row, moved_row(y, d), |a, b| a & !b);
+                result += moved_row(y, d)
+                    .iter()
+                    .map(|w| w.count_ones() as i32)
+                    .sum::<i32>();
+            }
+            let arrivals = [
+                moved_row(y + 1, Direction::North).clone(),
+                moved_row(y.wrapping_sub(1), Direction::South).clone(),
+                shift_west(moved_row(y, Direction::West)),
+                shift_east(moved_row(y, Direction::East)),
+            ];
+            for a in arrivals {
+                new_row = combine(&new_row, &a, |a, b| a | b);
+            }
+            new_rows.push(new_row);
+        }
+        self.rows = new_rows;
+
+        Ok(result)
+    }
+}
+
+fn empty_ground(map: &Input) -> i32 {
+    let size = map
+        .bounding_box()
+        .map_or(Point::default(), |bounds| bounds.size());
+    let area = size.x() * size.y();
+    area - map.len() as i32
+}
+
 #[aoc(day23, part1)]
 fn part1(input: &Input) -> Result<Output> {
-    let mut map = input.clone();
-    let mut directions = Direction::iter().cycle();
-    for _round in 1..=10 {
-        round(&mut map, &directions)?;
-        directions.next();
+    let map = simulate(input, &Rules::default(), RunLength::Rounds(10), false)?.map;
+    // print_map(&map);
+    Ok(empty_ground(&map))
+}
 
-        // println!("== End of Round {} ==", _round);
-        // print_map(&map);
+#[aoc(day23, part1, bits)]
+fn part1_bits(input: &Input) -> Result<Output> {
+    let rules = Rules::default();
+    let mut grid = BitGrid::new(input);
+    for round_idx in 0..10 {
+        grid.round(&rules, round_idx)?;
     }
-    let (min, max) = bounding_box(&map);
-    // print_map(&map);
-    let area = (max.0 - min.0 + 1) * (max.1 - min.1 + 1);
-    Ok(area - map.len() as i32)
+    Ok(empty_ground(&grid.to_map()))
 }
 
 #[aoc(day23, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let mut map = input.clone();
-    let mut directions = Direction::iter().cycle();
-    let mut idx = 1;
-    while round(&mut map, &directions)? != 0 {
-        idx += 1;
-        directions.next();
+    let sim = simulate(input, &Rules::default(), RunLength::UntilStable, false)?;
+    Ok(sim.rounds as i32)
+}
+
+#[aoc(day23, part2, bits)]
+fn part2_bits(input: &Input) -> Result<Output> {
+    let rules = Rules::default();
+    let mut grid = BitGrid::new(input);
+    let mut round_idx = 0;
+    while grid.round(&rules, round_idx)? != 0 {
+        round_idx += 1;
     }
-    Ok(idx)
+    Ok(round_idx as i32 + 1)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::Lcg;
 
     const INPUT_STR: &str = "....#..
 ..###.#
@@ -187,19 +494,20 @@ mod test {
     #[test]
     fn part1_test() -> Result<()> {
         let mut input = input_generator(INPUT_STR2)?;
-        let mut directions = Direction::iter().cycle();
-        print_map(&input);
-        round(&mut input, &directions)?;
-        print_map(&input);
-        directions.next();
-        round(&mut input, &directions)?;
-        print_map(&input);
-        directions.next();
-        round(&mut input, &directions)?;
-        print_map(&input);
-        let mut input = input_generator(INPUT_STR)?;
-        // todo!();
+        let rules = Rules::default();
+        assert_eq!(render_map(&input), "##\n#.\n..\n##\n");
+        round(&mut input, &rules, 0)?;
+        assert_eq!(render_map(&input), "##\n..\n#.\n.#\n#.\n");
+        round(&mut input, &rules, 1)?;
+        assert_eq!(render_map(&input), ".##.\n#...\n...#\n....\n.#..\n");
+        round(&mut input, &rules, 2)?;
+        assert_eq!(
+            render_map(&input),
+            "..#..\n....#\n#....\n....#\n.....\n..#..\n"
+        );
+        let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, 110);
+        assert_eq!(part1_bits(&input)?, 110);
         Ok(())
     }
 
@@ -207,6 +515,72 @@ mod test {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 20);
+        assert_eq!(part2_bits(&input)?, 20);
+        Ok(())
+    }
+
+    #[test]
+    fn simulation_test() -> Result<()> {
+        let input = input_generator(INPUT_STR2)?;
+        let sim = simulate(&input, &Rules::default(), RunLength::UntilStable, true)?;
+        assert!(sim.stable);
+        assert_eq!(sim.rounds, 4);
+        assert_eq!(sim.frames.len(), 5);
+        assert_eq!(sim.frames[1], "== End of Round 1 ==\n##\n..\n#.\n.#\n#.\n");
+
+        let sim = simulate(&input, &Rules::default(), RunLength::Rounds(10), false)?;
+        assert!(sim.stable);
+        assert_eq!(sim.rounds, 10);
+        assert!(sim.frames.is_empty());
+
+        // The middle and bottom elves both want (2, 3)
+        let rules = Rules {
+            conflict: ConflictRule::FirstWins,
+            ..Rules::default()
+        };
+        let sim = simulate(&input, &rules, RunLength::Rounds(1), false)?;
+        assert!(sim.map.contains(&Point([2, 3])));
+        assert!(!sim.map.contains(&Point([2, 2])));
+        assert!(sim.map.contains(&Point([2, 4])));
+        Ok(())
+    }
+
+    #[test]
+    fn bit_grid_test() -> Result<()> {
+        // A reproducible forest of elves that is wider than one word
+        let mut rng = Lcg::new(0x2022_1223);
+        let mut input = Grid::default();
+        for y in 0..40 {
+            for x in -20..80 {
+                if rng.next_u31() >> 28 == 0 {
+                    input.insert(Point([x, y]), ());
+                }
+            }
+        }
+
+        for rules in [
+            Rules::default(),
+            Rules {
+                neighbourhood: Neighbourhood::VonNeumann,
+                ..Rules::default()
+            },
+            Rules {
+                order: vec![Direction::East, Direction::North, Direction::West],
+                neighbourhood: Neighbourhood::VonNeumann,
+                conflict: ConflictRule::AllStay,
+            },
+        ] {
+            let mut map = input.clone();
+            let mut grid = BitGrid::new(&input);
+            for round_idx in 0..1000 {
+                let moved = round(&mut map, &rules, round_idx)?;
+                assert_eq!(grid.round(&rules, round_idx)?, moved, "{:?}", rules);
+                assert_eq!(grid.to_map(), map);
+                if moved == 0 {
+                    break;
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -1,80 +1,97 @@
-use std::collections::HashMap;
-
-use anyhow::Result;
+use anyhow::{bail, ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 
-type Input = Vec<(HashMap<char, u32>, HashMap<char, u32>)>;
-type Output = i32;
+/// One bit per item, with bit `n` set for the item of priority `n`.
+type Items = u64;
+type Input = Vec<Rucksack>;
+type Output = u32;
 
-#[aoc_generator(day3)]
-fn input_generator(input: &str) -> Result<Input> {
-    let mut result = vec![];
-    for l in input.lines() {
-        let mut c = l.chars();
-        let pocket_size = l.len() / 2;
-        let mut first = HashMap::new();
-        let mut second = HashMap::new();
-        for _ in 0..pocket_size {
-            *first.entry(c.next().unwrap()).or_default() += 1;
-        }
-        for _ in 0..pocket_size {
-            *second.entry(c.next().unwrap()).or_default() += 1;
-        }
-        result.push((first, second));
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rucksack {
+    compartments: Vec<Items>,
+}
+
+impl Rucksack {
+    fn parse(line: &str, compartments: usize) -> Result<Self> {
+        ensure!(compartments > 0, "Rucksacks need at least one compartment");
+        let items = line.chars().map(priority).collect::<Result<Vec<u32>>>()?;
+        ensure!(
+            items.len() % compartments == 0,
+            "{} items cannot be split into {} compartments",
+            items.len(),
+            compartments
+        );
+        let size = items.len() / compartments;
+        let compartments = (0..compartments)
+            .map(|idx| {
+                items[idx * size..(idx + 1) * size]
+                    .iter()
+                    .fold(0, |acc, p| acc | 1 << p)
+            })
+            .collect();
+        Ok(Self { compartments })
     }
 
-    Ok(result)
-}
+    fn all(&self) -> Items {
+        self.compartments.iter().fold(0, |acc, c| acc | c)
+    }
 
-fn priority(item: char) -> i32 {
-    if ('a'..='z').contains(&item) {
-        item as i32 - 'a' as i32 + 1
-    } else {
-        item as i32 - 'A' as i32 + 27
+    /// Items which appear in every compartment
+    fn shared(&self) -> Items {
+        self.compartments.iter().fold(Items::MAX, |acc, c| acc & c)
     }
 }
 
-fn merge_bags(elf: &(HashMap<char, u32>, HashMap<char, u32>)) -> HashMap<char, u32> {
-    let mut result = HashMap::new();
-    for e in elf.0.keys() {
-        result.insert(*e, 1);
+fn priority(item: char) -> Result<u32> {
+    match item {
+        'a'..='z' => Ok(item as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Ok(item as u32 - 'A' as u32 + 27),
+        _ => bail!("Unexpected item {:?}", item),
     }
-    for e in elf.1.keys() {
-        result.insert(*e, 1);
-    }
-    result
+}
+
+fn total_priority(items: Items) -> u32 {
+    (1..Items::BITS).filter(|p| items & (1 << p) != 0).sum()
+}
+
+fn parse_rucksacks(input: &str, compartments: usize) -> Result<Input> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| {
+            Rucksack::parse(l, compartments).with_context(|| format!("Line {}", idx + 1))
+        })
+        .collect()
+}
+
+/// The items carried by every elf in each group of `size` elves
+fn badges(input: &Input, size: usize) -> Result<Vec<Items>> {
+    ensure!(size > 0, "Groups need at least one elf");
+    ensure!(
+        input.len() % size == 0,
+        "{} elves cannot be split into groups of {}",
+        input.len(),
+        size
+    );
+    Ok(input
+        .chunks_exact(size)
+        .map(|group| group.iter().fold(Items::MAX, |acc, r| acc & r.all()))
+        .collect())
+}
+
+#[aoc_generator(day3)]
+fn input_generator(input: &str) -> Result<Input> {
+    parse_rucksacks(input, 2)
 }
 
 #[aoc(day3, part1)]
 fn part1(input: &Input) -> Result<Output> {
-    let mut result = 0;
-    for bag in input {
-        for item in bag.0.keys() {
-            if bag.1.contains_key(item) {
-                // println!("{} {}", item, priority(*item));
-                result += priority(*item);
-            }
-        }
-    }
-    Ok(result)
+    Ok(input.iter().map(|r| total_priority(r.shared())).sum())
 }
 
 #[aoc(day3, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let mut result = 0;
-    for group in input.chunks_exact(3) {
-        let items1 = merge_bags(&group[0]);
-        let items2 = merge_bags(&group[1]);
-        let items3 = merge_bags(&group[2]);
-
-        for guess in items1.keys() {
-            if items2.contains_key(guess) && items3.contains_key(guess) {
-                // println!("{}", guess);
-                result += priority(*guess);
-            }
-        }
-    }
-    Ok(result)
+    Ok(badges(input, 3)?.into_iter().map(total_priority).sum())
 }
 
 #[cfg(test)]
@@ -101,4 +118,29 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
         assert_eq!(part2(&input)?, 70);
         Ok(())
     }
+
+    #[test]
+    fn bitmask_test() -> Result<()> {
+        let err = input_generator("abcd\nabc").unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Line 2: 3 items cannot be split into 2 compartments"
+        );
+        let err = input_generator("abcd\nab-d").unwrap_err();
+        assert_eq!(format!("{:#}", err), "Line 2: Unexpected item '-'");
+
+        // Only 'a' is in all three thirds
+        let input = parse_rucksacks("abacaZ\nxyzxyzxyz", 3)?;
+        assert_eq!(input[0].shared(), 1 << 1);
+        assert_eq!(total_priority(input[1].shared()), 24 + 25 + 26);
+
+        // Groups of two and six
+        let input = input_generator(INPUT_STR)?;
+        assert!(badges(&input, 4).is_err());
+        let pairs = badges(&input, 2)?;
+        assert_eq!(pairs.len(), 3);
+        assert_ne!(pairs[0] & 1 << priority('r')?, 0);
+        assert_eq!(badges(&input, 6)?, vec![0]);
+        Ok(())
+    }
 }
@@ -1,46 +1,124 @@
 use std::ops::RangeInclusive;
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 
-type Input = Vec<(RangeInclusive<i32>, RangeInclusive<i32>)>;
+/// The sections assigned to a single elf
+type Sections = RangeInclusive<i32>;
+type Input = Vec<Vec<Sections>>;
 type Output = i32;
 
 #[aoc_generator(day4)]
 fn input_generator(input: &str) -> Result<Input> {
     let mut result = vec![];
 
-    for l in input.lines() {
-        let (elf1, elf2) = l.split_once(',').context("No comma")?;
-        result.push((assignments_to_range(elf1)?, assignments_to_range(elf2)?));
+    for (idx, l) in input.lines().enumerate() {
+        let group = l
+            .split(',')
+            .map(assignments_to_range)
+            .collect::<Result<Vec<Sections>>>()
+            .with_context(|| format!("Line {}", idx + 1))?;
+        result.push(group);
     }
 
     Ok(result)
 }
 
-fn assignments_to_range(assignments: &str) -> Result<RangeInclusive<i32>> {
+fn assignments_to_range(assignments: &str) -> Result<Sections> {
     let (start, end) = assignments.split_once('-').context("No dash")?;
     let start: i32 = start.parse()?;
     let end: i32 = end.parse()?;
+    ensure!(start <= end, "Backwards assignment {}", assignments);
     Ok(RangeInclusive::new(start, end))
 }
 
-fn contains(r1: &RangeInclusive<i32>, r2: &RangeInclusive<i32>) -> bool {
+fn contains(r1: &Sections, r2: &Sections) -> bool {
     r1.start() <= r2.start() && r1.end() >= r2.end()
 }
 
-fn overlaps(r1: &RangeInclusive<i32>, r2: &RangeInclusive<i32>) -> bool {
+fn overlaps(r1: &Sections, r2: &Sections) -> bool {
     r1.contains(r2.start())
         || r1.contains(r2.end())
         || r2.contains(r1.start())
         || r2.contains(r1.end())
 }
 
+fn section_count(r: &Sections) -> i32 {
+    (r.end() - r.start() + 1).max(0)
+}
+
+/// Sorted, non-overlapping and non-adjacent ranges covering the same sections as `ranges`
+fn merge<'a>(ranges: impl IntoIterator<Item = &'a Sections>) -> Vec<Sections> {
+    let mut ranges: Vec<Sections> = ranges.into_iter().cloned().collect();
+    ranges.sort_by_key(|r| *r.start());
+    let mut result: Vec<Sections> = vec![];
+    for r in ranges {
+        match result.last_mut() {
+            Some(last) if *r.start() <= last.end() + 1 => {
+                if r.end() > last.end() {
+                    *last = *last.start()..=*r.end();
+                }
+            }
+            _ => result.push(r),
+        }
+    }
+    result
+}
+
+/// Elves whose sections are all covered by the rest of the group.
+/// Elves with identical assignments are each redundant, though not both at once.
+fn redundant(group: &[Sections]) -> Vec<usize> {
+    (0..group.len())
+        .filter(|idx| {
+            let others = merge(
+                group
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| other != idx)
+                    .map(|(_, r)| r),
+            );
+            others.iter().any(|r| contains(r, &group[*idx]))
+        })
+        .collect()
+}
+
+/// Sections within `span` which no elf in the group is assigned to
+fn uncovered(group: &[Sections], span: &Sections) -> i32 {
+    let covered: i32 = merge(group)
+        .iter()
+        .map(|r| section_count(&(*r.start().max(span.start())..=*r.end().min(span.end()))))
+        .sum();
+    section_count(span) - covered
+}
+
+/// The fewest elves whose assignments cover all of `span`, or `None` if the whole group can't.
+fn minimal_cover(group: &[Sections], span: &Sections) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..group.len()).collect();
+    order.sort_by_key(|idx| *group[*idx].start());
+
+    let mut result = vec![];
+    let mut next = *span.start();
+    let mut candidates = order.into_iter().peekable();
+    let mut best: Option<usize> = None;
+    while next <= *span.end() {
+        while let Some(idx) = candidates.next_if(|idx| *group[*idx].start() <= next) {
+            if best.map_or(true, |b| group[idx].end() > group[b].end()) {
+                best = Some(idx);
+            }
+        }
+        let chosen = best.filter(|b| *group[*b].end() >= next)?;
+        result.push(chosen);
+        next = group[chosen].end() + 1;
+    }
+    Some(result)
+}
+
 #[aoc(day4, part1)]
 fn part1(input: &Input) -> Result<Output> {
     let mut result = 0;
-    for pair in input {
-        if contains(&pair.0, &pair.1) || contains(&pair.1, &pair.0) {
+    for group in input {
+        if !redundant(group).is_empty() {
             result += 1;
         }
     }
@@ -50,8 +128,48 @@ fn part1(input: &Input) -> Result<Output> {
 #[aoc(day4, part2)]
 fn part2(input: &Input) -> Result<Output> {
     let mut result = 0;
-    for pair in input {
-        if overlaps(&pair.0, &pair.1) || contains(&pair.1, &pair.0) {
+    for group in input {
+        if group
+            .iter()
+            .tuple_combinations()
+            .any(|(r1, r2)| overlaps(r1, r2))
+        {
+            result += 1;
+        }
+    }
+    Ok(result)
+}
+
+/// A group has a redundant elf exactly when fewer elves than the whole group can cover its sections
+#[aoc(day4, part1, cover)]
+fn part1_cover(input: &Input) -> Result<Output> {
+    let mut result = 0;
+    for group in input {
+        let needed: Option<usize> = merge(group)
+            .iter()
+            .map(|span| minimal_cover(group, span).map(|cover| cover.len()))
+            .sum();
+        if needed.context("Merged sections should always be covered")? < group.len() {
+            result += 1;
+        }
+    }
+    Ok(result)
+}
+
+/// Some elves overlap exactly when the group covers fewer sections than it is assigned in total
+#[aoc(day4, part2, cover)]
+fn part2_cover(input: &Input) -> Result<Output> {
+    let mut result = 0;
+    for group in input {
+        let (Some(start), Some(end)) = (
+            group.iter().map(|r| *r.start()).min(),
+            group.iter().map(|r| *r.end()).max(),
+        ) else {
+            continue;
+        };
+        let span = start..=end;
+        let covered = section_count(&span) - uncovered(group, &span);
+        if group.iter().map(section_count).sum::<i32>() > covered {
             result += 1;
         }
     }
@@ -73,6 +191,7 @@ mod test {
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, 2);
+        assert_eq!(part1_cover(&input)?, 2);
         Ok(())
     }
 
@@ -80,6 +199,35 @@ mod test {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 4);
+        assert_eq!(part2_cover(&input)?, 4);
+
+        // Groups of three, including one with a gap and one where only the outer elves meet
+        let input = input_generator("1-2,4-5,7-8\n1-3,3-4,6-9\n1-9,2-2,5-6\n1-4,5-6,3-8")?;
+        assert_eq!(part1(&input)?, part1_cover(&input)?);
+        assert_eq!(part2(&input)?, 3);
+        assert_eq!(part2_cover(&input)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn range_algebra_test() -> Result<()> {
+        let input = input_generator("2-5,6-8,3-7\n1-3,3-3,2-5,5-9\n4-4,4-4")?;
+        assert_eq!(redundant(&input[0]), vec![2]);
+        assert_eq!(redundant(&input[1]), vec![1]);
+        assert_eq!(redundant(&input[2]), vec![0, 1]);
+        assert_eq!(part1(&input)?, 3);
+
+        assert_eq!(merge(&input[0]), vec![2..=8]);
+        assert_eq!(uncovered(&input[0], &(0..=10)), 4);
+        assert_eq!(uncovered(&input[0], &(3..=7)), 0);
+        assert_eq!(uncovered(&input[2], &(1..=9)), 8);
+
+        assert_eq!(minimal_cover(&input[0], &(2..=8)), Some(vec![0, 1]));
+        assert_eq!(minimal_cover(&input[1], &(1..=9)), Some(vec![0, 2, 3]));
+        assert_eq!(minimal_cover(&input[1], &(1..=10)), None);
+        assert_eq!(minimal_cover(&input[0], &(1..=4)), None);
+
+        assert!(input_generator("4-2,1-1").is_err());
         Ok(())
     }
 }
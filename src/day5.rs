@@ -1,11 +1,11 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
 use regex::Regex;
 
-type Input = (Vec<Vec<char>>, Vec<Move>);
+type Input = (Stacks, Vec<Move>);
 type Output = String;
 
 #[derive(Debug, Clone, Copy)]
@@ -34,27 +34,241 @@ impl FromStr for Move {
     }
 }
 
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count, self.source, self.dest
+        )
+    }
+}
+
+/// Crate labels are usually a single letter, but anything between the brackets is accepted.
+type Crate = String;
+/// Stack 0 is always empty so that stacks can be indexed the same way moves number them.
+type Stacks = Vec<Vec<Crate>>;
+
+trait Crane {
+    /// Most crates which can be lifted at once, or `None` if there is no limit
+    fn capacity(&self) -> Option<usize>;
+
+    /// Applies a single move, leaving `stacks` untouched if the move is invalid.
+    fn apply(&self, stacks: &mut Stacks, m: &Move) -> Result<()> {
+        let chunk = self.capacity().unwrap_or(m.count);
+        ensure!(
+            chunk > 0 || m.count == 0,
+            "Crane cannot lift any crates for {}",
+            m
+        );
+        if m.source == 0 || m.source >= stacks.len() {
+            bail!("Invalid source stack for {}", m);
+        }
+        if m.dest == 0 || m.dest >= stacks.len() {
+            bail!("Invalid destination stack for {}", m);
+        }
+        let available = stacks[m.source].len();
+        if available < m.count {
+            bail!(
+                "Not enough crates for {}: stack {} only has {}",
+                m,
+                m.source,
+                available
+            );
+        }
+
+        let mut remaining = m.count;
+        while remaining > 0 {
+            let lifted = chunk.min(remaining);
+            let source = &mut stacks[m.source];
+            let current = source.split_off(source.len() - lifted);
+            stacks[m.dest].extend(current);
+            remaining -= lifted;
+        }
+        Ok(())
+    }
+}
+
+/// Moves one crate at a time
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn capacity(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+/// Moves all crates at once, keeping their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Moves up to `capacity` crates at once, keeping the order within each lift
+struct CappedCrateMover {
+    capacity: usize,
+}
+
+impl Crane for CappedCrateMover {
+    fn capacity(&self) -> Option<usize> {
+        Some(self.capacity)
+    }
+}
+
+fn run_crane<C: Crane>(crane: &C, input: &Input) -> Result<Stacks> {
+    let mut stacks = input.0.clone();
+    for m in &input.1 {
+        // println!("{:?}", m);
+        crane.apply(&mut stacks, m)?;
+    }
+    Ok(stacks)
+}
+
+fn top_crates(stacks: &Stacks) -> String {
+    stacks
+        .iter()
+        .filter_map(|s| s.last())
+        .map(|c| c.as_str())
+        .collect()
+}
+
+fn parse_error<M: Display>(line: usize, column: usize, msg: M) -> anyhow::Error {
+    anyhow!("Line {}, column {}: {}", line, column, msg)
+}
+
+/// Returns the (first column, last column, label) of every crate in a drawing row.
+/// Columns are zero-based, but errors report them one-based.
+fn parse_crate_row(line_no: usize, line: &str) -> Result<Vec<(usize, usize, Crate)>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = vec![];
+    let mut idx = 0;
+    while idx < chars.len() {
+        match chars[idx] {
+            ' ' => idx += 1,
+            '[' => {
+                let end = chars[idx + 1..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|p| p + idx + 1)
+                    .ok_or_else(|| parse_error(line_no, idx + 1, "Unclosed '['"))?;
+                let label: String = chars[idx + 1..end].iter().collect();
+                if let Some(p) = label.find(|c: char| c == '[' || c.is_whitespace()) {
+                    bail!(parse_error(
+                        line_no,
+                        idx + 2 + label[..p].chars().count(),
+                        "Unexpected character in crate label"
+                    ));
+                }
+                if label.is_empty() {
+                    bail!(parse_error(line_no, idx + 1, "Empty crate label"));
+                }
+                result.push((idx, end, label));
+                idx = end + 1;
+            }
+            c => bail!(parse_error(line_no, idx + 1, format!("Unexpected {:?}", c))),
+        }
+    }
+    Ok(result)
+}
+
+/// Returns the (first column, last column) of each stack number, which must count up from 1.
+fn parse_stack_ids(line_no: usize, line: &str) -> Result<Vec<(usize, usize)>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = vec![];
+    let mut idx = 0;
+    while idx < chars.len() {
+        if chars[idx] == ' ' {
+            idx += 1;
+        } else if chars[idx].is_ascii_digit() {
+            let start = idx;
+            while idx < chars.len() && chars[idx].is_ascii_digit() {
+                idx += 1;
+            }
+            let id: usize = chars[start..idx]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|e| parse_error(line_no, start + 1, e))?;
+            if id != result.len() + 1 {
+                bail!(parse_error(
+                    line_no,
+                    start + 1,
+                    format!("Expected stack {} but found {}", result.len() + 1, id)
+                ));
+            }
+            result.push((start, idx - 1));
+        } else {
+            bail!(parse_error(
+                line_no,
+                idx + 1,
+                format!("Unexpected {:?}", chars[idx])
+            ));
+        }
+    }
+    if result.is_empty() {
+        bail!(parse_error(line_no, 1, "No stack numbers"));
+    }
+    Ok(result)
+}
+
 #[aoc_generator(day5)]
 fn input_generator(input: &str) -> Result<Input> {
-    let mut lines = input.lines();
-    let mut stacks = vec![];
-    stacks.push(vec![]);
-    let mut moves = vec![];
+    let mut lines = input.lines().enumerate().map(|(idx, l)| (idx + 1, l));
 
-    loop {
-        let l = lines.next().unwrap();
-        if l.contains('1') {
-            lines.next();
-            break;
+    // Crates can only be placed once we know where the stacks are, so hold on to the rows
+    // until we hit the number row. Anything without a bracket is taken to be the number row.
+    let mut rows = vec![];
+    let (ids_line, ids) = loop {
+        let (line_no, l) = lines.next().context("Missing stack number row")?;
+        if l.contains('[') {
+            rows.push((line_no, parse_crate_row(line_no, l)?));
+        } else {
+            break (line_no, parse_stack_ids(line_no, l)?);
         }
-        let chars: Vec<char> = l.chars().collect();
-        for (idx, chunk) in chars.chunks(4).enumerate() {
-            // println!("Chunk {} is {:?}", idx, chunk);
-            if stacks.len() <= idx + 1 {
-                stacks.push(vec![]);
+    };
+    if let Some((line_no, l)) = lines.next() {
+        ensure!(
+            l.trim().is_empty(),
+            parse_error(line_no, 1, "Expected a blank line after the stack numbers")
+        );
+    }
+
+    let mut stacks: Stacks = vec![vec![]; ids.len() + 1];
+    for (line_no, row) in rows {
+        let mut filled = vec![false; ids.len()];
+        for (start, end, label) in row {
+            let mut under = ids
+                .iter()
+                .enumerate()
+                .filter(|(_, (id_start, id_end))| *id_start <= end && start <= *id_end);
+            let idx = match (under.next(), under.next()) {
+                (Some((idx, _)), None) => idx,
+                _ => bail!(parse_error(
+                    line_no,
+                    start + 1,
+                    format!(
+                        "Crate [{}] does not line up with exactly one stack number on line {}",
+                        label, ids_line
+                    )
+                )),
+            };
+            if filled[idx] {
+                bail!(parse_error(line_no, start + 1, "Two crates on one stack"));
             }
-            if chunk[1] != ' ' {
-                stacks[idx + 1].push(chunk[1]);
+            filled[idx] = true;
+            stacks[idx + 1].push(label);
+        }
+        // Rows are read from the top, so any stack already started must continue downwards
+        for (idx, filled) in filled.iter().enumerate() {
+            if !filled && !stacks[idx + 1].is_empty() {
+                bail!(parse_error(
+                    line_no,
+                    ids[idx].0 + 1,
+                    format!("Crate floating above stack {}", idx + 1)
+                ));
             }
         }
     }
@@ -65,55 +279,42 @@ fn input_generator(input: &str) -> Result<Input> {
 
     // println!("{:?}", stacks);
     // Now, read the rules
-    for l in lines {
-        moves.push(l.parse()?);
+    let mut moves = vec![];
+    for (line_no, l) in lines {
+        moves.push(l.parse().with_context(|| format!("Line {}", line_no))?);
     }
     Ok((stacks, moves))
 }
 
 #[aoc(day5, part1)]
 fn part1(input: &Input) -> Result<Output> {
-    let mut stacks = input.0.clone();
-
-    for m in &input.1 {
-        // println!("{:?}", m);
-
-        for _ in 0..m.count {
-            let c = stacks[m.source].pop().context("Underflow")?;
-            stacks[m.dest].push(c);
-        }
-    }
-    let mut result = String::new();
-    for s in &stacks {
-        result += &s.last().map(|c| c.to_string()).unwrap_or_default();
-    }
-    Ok(result)
+    Ok(top_crates(&run_crane(&CrateMover9000, input)?))
 }
 
 #[aoc(day5, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let mut stacks = input.0.clone();
+    Ok(top_crates(&run_crane(&CrateMover9001, input)?))
+}
 
-    for m in &input.1 {
-        // println!("{:?}", m);
+/// The CrateMover 9000 is a capped crane which lifts one crate at a time
+#[aoc(day5, part1, capped)]
+fn part1_capped(input: &Input) -> Result<Output> {
+    let crane = CappedCrateMover { capacity: 1 };
+    Ok(top_crates(&run_crane(&crane, input)?))
+}
 
-        let mut current = vec![];
-        for _ in 0..m.count {
-            let c = stacks[m.source].pop().context("Underflow")?;
-            current.push(c);
-        }
-        current.reverse();
-        stacks[m.dest].extend(current);
-    }
-    let mut result = String::new();
-    for s in &stacks {
-        result += &s.last().map(|c| c.to_string()).unwrap_or_default();
-    }
-    Ok(result)
+/// The CrateMover 9001 is a capped crane which can lift every crate in a move at once
+#[aoc(day5, part2, capped)]
+fn part2_capped(input: &Input) -> Result<Output> {
+    let capacity = input.1.iter().map(|m| m.count).max().unwrap_or(1);
+    let crane = CappedCrateMover { capacity };
+    Ok(top_crates(&run_crane(&crane, input)?))
 }
 
 #[cfg(test)]
 mod test {
+    use itertools::Itertools;
+
     use super::*;
 
     const INPUT_STR: &str = "    [D]    
@@ -130,6 +331,7 @@ move 1 from 1 to 2";
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, "CMZ");
+        assert_eq!(part1_capped(&input)?, "CMZ");
         Ok(())
     }
 
@@ -137,6 +339,80 @@ move 1 from 1 to 2";
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, "MCD");
+        assert_eq!(part2_capped(&input)?, "MCD");
+        Ok(())
+    }
+
+    #[test]
+    fn crane_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        assert_eq!(
+            top_crates(&run_crane(&CappedCrateMover { capacity: 1 }, &input)?),
+            "CMZ"
+        );
+        assert_eq!(
+            top_crates(&run_crane(&CappedCrateMover { capacity: 3 }, &input)?),
+            "MCD"
+        );
+        // The second move lifts [N, D] and then [Z]
+        assert_eq!(
+            top_crates(&run_crane(&CappedCrateMover { capacity: 2 }, &input)?),
+            "MCZ"
+        );
+
+        let mut stacks = input.0.clone();
+        let err = CrateMover9001
+            .apply(&mut stacks, &"move 4 from 2 to 1".parse()?)
+            .unwrap_err();
+        assert!(err.to_string().contains("move 4 from 2 to 1"));
+        assert!(CrateMover9000
+            .apply(&mut stacks, &"move 1 from 4 to 1".parse()?)
+            .is_err());
+        assert!(CrateMover9000
+            .apply(&mut stacks, &"move 1 from 1 to 0".parse()?)
+            .is_err());
+        assert_eq!(stacks, input.0);
+        Ok(())
+    }
+
+    #[test]
+    fn parser_test() -> Result<()> {
+        let input = input_generator("[AB]\n[CD] [EF]\n 1    2\n\nmove 1 from 1 to 2")?;
+        assert_eq!(input.0[1], vec!["CD", "AB"]);
+        assert_eq!(part1(&input)?, "CDAB");
+
+        // A crate labelled 1 is not the number row
+        let input = input_generator("[1] [2]\n 1   2\n\nmove 1 from 1 to 2")?;
+        assert_eq!(part1(&input)?, "1");
+
+        let crates = (0..10u8)
+            .map(|i| format!("[{}]", (b'A' + i) as char))
+            .join(" ");
+        let numbers = (1..=10).map(|i| format!(" {:<3}", i)).join("");
+        let input = input_generator(&format!("{}\n{}\n\nmove 1 from 10 to 1", crates, numbers))?;
+        assert_eq!(input.0.len(), 11);
+        assert_eq!(part1(&input)?, "JBCDEFGHI");
+
+        let errors = [
+            ("[A] x\n 1", "Line 1, column 5"),
+            ("[A\n 1", "Line 1, column 1"),
+            ("[A] []\n 1   2", "Line 1, column 5"),
+            ("[A] [B]\n 1   3", "Line 2, column 6"),
+            ("[A]\n    [B]\n 1   2", "Line 2, column 2"),
+            ("[A] [B]\n 1", "Line 1, column 5"),
+            ("[A]\n 1\nmove 1 from 1 to 1", "Line 3, column 1"),
+            ("[A]\n 1\n\nmove one", "Line 4"),
+            ("[A]", "Missing stack number row"),
+        ];
+        for (input, message) in errors {
+            let err = input_generator(input).unwrap_err();
+            assert!(
+                format!("{:#}", err).contains(message),
+                "{:?}: {:#}",
+                input,
+                err
+            );
+        }
         Ok(())
     }
 }
@@ -1,4 +1,9 @@
-use anyhow::{Context, Result};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use anyhow::{ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
@@ -22,6 +27,59 @@ fn find_start(seq: &str, window_size: usize) -> Option<usize> {
     None
 }
 
+/// Keeps the last `window` symbols of a stream along with how often each of them appears,
+/// so checking whether they are all different is O(1) per symbol.
+struct MarkerDetector<T> {
+    window: usize,
+    recent: VecDeque<T>,
+    counts: HashMap<T, usize>,
+    position: usize,
+}
+
+impl<T: Hash + Eq + Clone> MarkerDetector<T> {
+    fn new(window: usize) -> Result<Self> {
+        ensure!(window > 0, "Markers need a window of at least one symbol");
+        Ok(Self {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: HashMap::new(),
+            position: 0,
+        })
+    }
+
+    /// Reads the next symbol and returns how many have been read so far
+    /// if the last `window` of them are all different.
+    fn push(&mut self, symbol: T) -> Option<usize> {
+        self.position += 1;
+        self.recent.push_back(symbol.clone());
+        *self.counts.entry(symbol).or_default() += 1;
+        if self.recent.len() > self.window {
+            if let Some(old) = self.recent.pop_front() {
+                if let Some(count) = self.counts.get_mut(&old) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.counts.remove(&old);
+                    }
+                }
+            }
+        }
+        // Any symbol seen twice leaves fewer keys than the window holds
+        (self.recent.len() == self.window && self.counts.len() == self.window)
+            .then_some(self.position)
+    }
+}
+
+/// Every position at which a marker of `window` distinct symbols ends.
+/// Works on bytes, `char`s, or anything else which can be hashed.
+fn markers<I>(symbols: I, window: usize) -> Result<impl Iterator<Item = usize>>
+where
+    I: IntoIterator,
+    I::Item: Hash + Eq + Clone,
+{
+    let mut detector = MarkerDetector::new(window)?;
+    Ok(symbols.into_iter().filter_map(move |s| detector.push(s)))
+}
+
 #[aoc(day6, part1)]
 fn part1(input: &Input) -> Result<Output> {
     find_start(input, 4).context("No start")
@@ -32,6 +90,16 @@ fn part2(input: &Input) -> Result<Output> {
     find_start(input, 14).context("No start")
 }
 
+#[aoc(day6, part1, streaming)]
+fn part1_streaming(input: &Input) -> Result<Output> {
+    markers(input.bytes(), 4)?.next().context("No start")
+}
+
+#[aoc(day6, part2, streaming)]
+fn part2_streaming(input: &Input) -> Result<Output> {
+    markers(input.bytes(), 14)?.next().context("No start")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -46,6 +114,9 @@ mod test {
         assert_eq!(part1(&input)?, 7);
         assert_eq!(part1(&input_generator(INPUT2)?)?, 5);
         assert_eq!(part1(&input_generator(INPUT3)?)?, 6);
+        assert_eq!(part1_streaming(&input)?, 7);
+        assert_eq!(part1_streaming(&input_generator(INPUT2)?)?, 5);
+        assert_eq!(part1_streaming(&input_generator(INPUT3)?)?, 6);
         Ok(())
     }
 
@@ -53,6 +124,32 @@ mod test {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 19);
+        assert_eq!(part2_streaming(&input)?, 19);
+        Ok(())
+    }
+
+    #[test]
+    fn markers_test() -> Result<()> {
+        for window in 1..6 {
+            let chars = INPUT_STR.chars().collect_vec();
+            let expected = chars
+                .windows(window)
+                .enumerate()
+                .filter(|(_, w)| w.iter().all_unique())
+                .map(|(idx, _)| idx + window)
+                .collect_vec();
+            assert_eq!(markers(INPUT_STR.bytes(), window)?.collect_vec(), expected);
+        }
+
+        // Multi-byte characters are single symbols when read as chars
+        assert_eq!(markers("ééaéèüaa".chars(), 3)?.collect_vec(), vec![5, 6, 7]);
+        assert_eq!(markers("ééé".chars(), 2)?.next(), None);
+        assert_eq!(markers("ééé".bytes(), 2)?.next(), Some(2));
+
+        // There are only two symbols, so no window of three can be distinct
+        assert_eq!(markers("abababab".chars(), 3)?.next(), None);
+        assert_eq!(markers("".bytes(), 4)?.next(), None);
+        assert!(markers(INPUT_STR.bytes(), 0).is_err());
         Ok(())
     }
 }
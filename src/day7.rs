@@ -1,13 +1,26 @@
 use std::{collections::HashMap, fmt::Display, str::FromStr};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_while_m_n},
+    character::complete::{char, i64, multispace0},
+    combinator::{all_consuming, map, map_opt, value},
+    multi::separated_list0,
+    sequence::{delimited, preceded, separated_pair},
+    IResult,
+};
 use once_cell::unsync::OnceCell;
+use strum::EnumString;
 
 type Input = INode;
 type Output = usize;
 
+const DISK_SIZE: usize = 70000000;
+const REQUIRED_SPACE: usize = 30000000;
+
 // Metadata common between files and dirs
 #[derive(Debug, Clone)]
 struct INode {
@@ -71,6 +84,26 @@ impl Display for INode {
 }
 
 impl INode {
+    fn new_dir(name: &str) -> Self {
+        INode {
+            name: name.to_string(),
+            size_cell: OnceCell::new(),
+            inode_type: INodeType::Dir(HashMap::new()),
+        }
+    }
+
+    /// Looks up an absolute (or root-relative) path such as `/a/e`
+    fn get(&self, path: &str) -> Option<&INode> {
+        let mut node = self;
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            node = match &node.inode_type {
+                INodeType::Dir(children) => children.get(name)?,
+                INodeType::File => return None,
+            };
+        }
+        Some(node)
+    }
+
     fn size(&self) -> usize {
         match &self.inode_type {
             INodeType::File => *self.size_cell.get().unwrap(),
@@ -96,6 +129,343 @@ impl INode {
         }
         result
     }
+
+    fn is_dir(&self) -> bool {
+        matches!(self.inode_type, INodeType::Dir(_))
+    }
+
+    /// This node and everything below it along with their paths, in name order
+    fn walk(&self, path: &str) -> Vec<(String, &INode)> {
+        let mut result = vec![(path.to_string(), self)];
+        if let INodeType::Dir(children) = &self.inode_type {
+            for (name, child) in children.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+                result.extend(child.walk(&join_path(path, name)));
+            }
+        }
+        result
+    }
+
+    /// Directory sizes, largest first, like `du -h | sort -rh`
+    fn du(&self, human: bool) -> String {
+        self.walk("/")
+            .into_iter()
+            .filter(|(_, n)| n.is_dir())
+            .sorted_by(|a, b| b.1.size().cmp(&a.1.size()).then_with(|| a.0.cmp(&b.0)))
+            .map(|(path, n)| format!("{}\t{}\n", format_size(n.size(), human), path))
+            .collect()
+    }
+
+    /// Renders the tree like `tree --du`, with cumulative sizes for directories
+    fn tree(&self, human: bool) -> String {
+        let mut result = format!("{} ({})\n", self.name, format_size(self.size(), human));
+        self.tree_children("", human, &mut result);
+        result
+    }
+
+    fn tree_children(&self, prefix: &str, human: bool, result: &mut String) {
+        if let INodeType::Dir(children) = &self.inode_type {
+            for (idx, (name, child)) in children.iter().sorted_by(|a, b| a.0.cmp(b.0)).enumerate() {
+                let last = idx + 1 == children.len();
+                *result += &format!(
+                    "{}{}{} ({})\n",
+                    prefix,
+                    if last { "└── " } else { "├── " },
+                    name,
+                    format_size(child.size(), human)
+                );
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                child.tree_children(&prefix, human, result);
+            }
+        }
+    }
+
+    /// Smallest directory which leaves at least `required` bytes free on a disk of size `disk` once deleted.
+    /// `None` if there is already enough free space.
+    fn smallest_to_free(&self, disk: usize, required: usize) -> Result<Option<(String, usize)>> {
+        let free = disk.saturating_sub(self.size());
+        let needed = required.saturating_sub(free);
+        if needed == 0 {
+            return Ok(None);
+        }
+        self.walk("/")
+            .into_iter()
+            .filter(|(_, n)| n.is_dir())
+            .map(|(path, n)| (path, n.size()))
+            .filter(|(_, size)| *size >= needed)
+            .min_by_key(|(_, size)| *size)
+            .context("No directory is large enough")
+            .map(Some)
+    }
+}
+
+/// Formats sizes the way `du -h` does, rounding up in powers of 1024
+fn format_size(size: usize, human: bool) -> String {
+    if !human || size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64;
+    let mut unit = 'K';
+    for u in ['K', 'M', 'G', 'T', 'P'] {
+        value /= 1024.0;
+        unit = u;
+        if value < 1024.0 {
+            break;
+        }
+    }
+    let rounded = (value * 10.0).ceil() / 10.0;
+    if rounded < 10.0 {
+        format!("{:.1}{}", rounded, unit)
+    } else {
+        format!("{}{}", value.ceil(), unit)
+    }
+}
+
+/// Just enough JSON to store an `INode` tree. Numbers must be integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl FromStr for Json {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_consuming(nom_json_value)(s) {
+            Ok((_, json)) => Ok(json),
+            Err(e) => bail!("Error parsing JSON: {}", e),
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_json_string(f, s),
+            Json::Array(v) => write!(f, "[{}]", v.iter().join(",")),
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn nom_json_string(s: &str) -> IResult<&str, String> {
+    let mut escape = alt((
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('/', char('/')),
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        value('\u{8}', char('b')),
+        value('\u{c}', char('f')),
+        map_opt(
+            preceded(
+                char('u'),
+                take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+            ),
+            |hex| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+        ),
+    ));
+    let mut result = String::new();
+    let (mut s, _) = char('"')(s)?;
+    loop {
+        if let Ok((rest, _)) = char::<&str, nom::error::Error<&str>>('"')(s) {
+            return Ok((rest, result));
+        }
+        if let Ok((rest, _)) = char::<&str, nom::error::Error<&str>>('\\')(s) {
+            let (rest, c) = escape(rest)?;
+            result.push(c);
+            s = rest;
+        } else {
+            let (rest, text) = is_not("\"\\")(s)?;
+            result += text;
+            s = rest;
+        }
+    }
+}
+
+fn nom_json_value(s: &str) -> IResult<&str, Json> {
+    delimited(
+        multispace0,
+        alt((
+            value(Json::Null, tag("null")),
+            value(Json::Bool(true), tag("true")),
+            value(Json::Bool(false), tag("false")),
+            map(i64, Json::Number),
+            map(nom_json_string, Json::String),
+            map(
+                delimited(
+                    char('['),
+                    separated_list0(char(','), nom_json_value),
+                    preceded(multispace0, char(']')),
+                ),
+                Json::Array,
+            ),
+            map(
+                delimited(
+                    char('{'),
+                    separated_list0(
+                        char(','),
+                        separated_pair(
+                            delimited(multispace0, nom_json_string, multispace0),
+                            char(':'),
+                            nom_json_value,
+                        ),
+                    ),
+                    preceded(multispace0, char('}')),
+                ),
+                Json::Object,
+            ),
+        )),
+        multispace0,
+    )(s)
+}
+
+impl INode {
+    /// Files are `{"name":..,"type":"file","size":..}` and directories are
+    /// `{"name":..,"type":"dir","children":[..]}` with children in name order.
+    fn to_json(&self) -> Json {
+        let mut fields = vec![("name".to_string(), Json::String(self.name.clone()))];
+        match &self.inode_type {
+            INodeType::File => {
+                fields.push(("type".to_string(), Json::String("file".to_string())));
+                fields.push(("size".to_string(), Json::Number(self.size() as i64)));
+            }
+            INodeType::Dir(children) => {
+                fields.push(("type".to_string(), Json::String("dir".to_string())));
+                let children = children
+                    .iter()
+                    .sorted_by(|a, b| a.0.cmp(b.0))
+                    .map(|(_, c)| c.to_json())
+                    .collect();
+                fields.push(("children".to_string(), Json::Array(children)));
+            }
+        }
+        Json::Object(fields)
+    }
+
+    fn from_json(json: &Json) -> Result<Self> {
+        let fields = match json {
+            Json::Object(fields) => fields,
+            _ => bail!("Expected an object but found {}", json),
+        };
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value)
+                .with_context(|| format!("Missing {:?} in {}", name, json))
+        };
+        let name = match field("name")? {
+            Json::String(name) => name,
+            other => bail!("Name must be a string, not {}", other),
+        };
+
+        match field("type")? {
+            Json::String(t) if t == "file" => {
+                let size = match field("size")? {
+                    Json::Number(size) => usize::try_from(*size)?,
+                    other => bail!("Size of {} must be a number, not {}", name, other),
+                };
+                Ok(INode {
+                    name: name.to_string(),
+                    size_cell: OnceCell::with_value(size),
+                    inode_type: INodeType::File,
+                })
+            }
+            Json::String(t) if t == "dir" => {
+                let mut children = HashMap::new();
+                let list = match field("children")? {
+                    Json::Array(list) => list,
+                    other => bail!("Children of {} must be an array, not {}", name, other),
+                };
+                for child in list {
+                    let child = INode::from_json(child)?;
+                    // The transcript splits on whitespace and `cd` treats `/`, `.` and `..` specially
+                    ensure!(
+                        !child.name.is_empty()
+                            && !child.name.contains(|c: char| c.is_whitespace() || c == '/')
+                            && child.name != "."
+                            && child.name != "..",
+                        "Invalid name {:?} in {}",
+                        child.name,
+                        name
+                    );
+                    ensure!(
+                        !children.contains_key(&child.name),
+                        "Duplicate entry {} in {}",
+                        child.name,
+                        name
+                    );
+                    children.insert(child.name.to_string(), child);
+                }
+                Ok(INode {
+                    name: name.to_string(),
+                    size_cell: OnceCell::new(),
+                    inode_type: INodeType::Dir(children),
+                })
+            }
+            other => bail!("Unknown type {} for {}", other, name),
+        }
+    }
+
+    /// A transcript which `input_generator` turns back into this tree
+    fn to_transcript(&self) -> String {
+        let mut result = "$ cd /\n".to_string();
+        self.transcript_listing(&mut result);
+        result
+    }
+
+    fn transcript_listing(&self, result: &mut String) {
+        if let INodeType::Dir(children) = &self.inode_type {
+            *result += "$ ls\n";
+            let children = children.values().sorted_by(|a, b| a.name.cmp(&b.name));
+            for child in children.clone() {
+                match child.inode_type {
+                    INodeType::File => *result += &format!("{} {}\n", child.size(), child.name),
+                    INodeType::Dir(_) => *result += &format!("dir {}\n", child.name),
+                }
+            }
+            for child in children.filter(|c| c.is_dir()) {
+                *result += &format!("$ cd {}\n", child.name);
+                child.transcript_listing(result);
+                *result += "$ cd ..\n";
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -118,64 +488,161 @@ impl FromStr for Cmd {
     }
 }
 
-#[aoc_generator(day7)]
-fn input_generator(input: &str) -> Result<Input> {
-    let items: Vec<OutputLine> = input
-        .lines()
-        .map(|l| l.parse())
-        .collect::<Result<Vec<OutputLine>>>()?;
-
-    let mut item_iter = items.iter();
-    item_iter.next();
-    parse_subinput("/", &mut item_iter)
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Replays a terminal transcript one line at a time, tracking the current directory.
+#[derive(Debug, Clone)]
+struct Shell {
+    root: INode,
+    cwd: Vec<String>,
+    /// Whether the last command was `ls`, so output lines are expected
+    listing: bool,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self {
+            root: INode::new_dir("/"),
+            cwd: vec![],
+            listing: false,
+        }
+    }
 }
 
-fn parse_subinput(name: &str, items: &mut std::slice::Iter<OutputLine>) -> Result<Input> {
-    // We're just going to assume we don't go into the same directory twice
-    let mut children: HashMap<String, INode> = HashMap::new();
-
-    while let Some(entry) = items.next() {
-        if let OutputLine::Ls(node) = entry {
-            children
-                .entry(node.name.to_string())
-                .or_insert_with(|| node.clone());
-        } else if let OutputLine::Cmd(Cmd::Cd(dir)) = entry {
-            if dir == ".." {
-                break;
+impl Shell {
+    fn cwd_path(&self) -> String {
+        format!("/{}", self.cwd.join("/"))
+    }
+
+    /// Children of the current directory.
+    /// Any cached sizes along the way are cleared as the caller may be about to change them.
+    fn cwd_children(&mut self) -> Result<&mut HashMap<String, INode>> {
+        let mut node = &mut self.root;
+        node.size_cell = OnceCell::new();
+        for name in &self.cwd {
+            node = match &mut node.inode_type {
+                INodeType::Dir(children) => children.get_mut(name).context("Missing directory")?,
+                INodeType::File => bail!("Not a directory"),
+            };
+            node.size_cell = OnceCell::new();
+        }
+        match &mut node.inode_type {
+            INodeType::Dir(children) => Ok(children),
+            INodeType::File => bail!("Not a directory"),
+        }
+    }
+
+    fn replay(&mut self, line: &OutputLine) -> Result<()> {
+        match line {
+            OutputLine::Cmd(Cmd::Ls) => self.listing = true,
+            OutputLine::Cmd(Cmd::Cd(dir)) => {
+                self.listing = false;
+                match dir.as_str() {
+                    "/" => self.cwd.clear(),
+                    ".." => {
+                        self.cwd.pop();
+                    }
+                    _ => {
+                        let path = join_path(&self.cwd_path(), dir);
+                        let child = self
+                            .cwd_children()?
+                            .entry(dir.to_string())
+                            .or_insert_with(|| INode::new_dir(dir));
+                        if let INodeType::File = child.inode_type {
+                            bail!("Cannot cd into file {}", path);
+                        }
+                        self.cwd.push(dir.to_string());
+                    }
+                }
             }
-            let child = parse_subinput(dir, items)?;
-            if let Some(current) = children.get_mut(dir) {
-                current.inode_type = child.inode_type.clone();
-            } else {
-                children.insert(dir.to_string(), child);
+            OutputLine::Ls(node) => {
+                ensure!(self.listing, "Output without ls");
+                let path = join_path(&self.cwd_path(), &node.name);
+                let children = self.cwd_children()?;
+                if let Some(existing) = children.get(&node.name) {
+                    match (&existing.inode_type, &node.inode_type) {
+                        (INodeType::File, INodeType::File) => ensure!(
+                            existing.size() == node.size(),
+                            "Conflicting sizes for {}: {} and {}",
+                            path,
+                            existing.size(),
+                            node.size()
+                        ),
+                        (INodeType::Dir(_), INodeType::Dir(_)) => {}
+                        _ => bail!("{} is listed as both a file and a directory", path),
+                    }
+                } else {
+                    children.insert(node.name.to_string(), node.clone());
+                }
             }
         }
+        Ok(())
     }
+}
+
+fn replay_transcript(input: &str) -> Result<Shell> {
+    let mut shell = Shell::default();
+    for (idx, l) in input.lines().enumerate() {
+        let line = l.parse().with_context(|| format!("Line {}", idx + 1))?;
+        shell
+            .replay(&line)
+            .with_context(|| format!("Line {}", idx + 1))?;
+    }
+    Ok(shell)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum TreeFormat {
+    Transcript,
+    Json,
+    /// Like `tree --du`; can only be written
+    Tree,
+    /// Like `du | sort -rn`; can only be written
+    Du,
+}
 
-    Ok(Input {
-        name: name.to_string(),
-        size_cell: OnceCell::new(),
-        inode_type: INodeType::Dir(children),
+/// Reads a filesystem in the `from` format and writes the directory at `path` out in the `to` format.
+/// `human` gives `tree` and `du` sizes like `du -h` does.
+pub fn convert_filesystem(
+    input: &str,
+    from: TreeFormat,
+    to: TreeFormat,
+    path: &str,
+    human: bool,
+) -> Result<String> {
+    let root = match from {
+        TreeFormat::Transcript => input_generator(input)?,
+        TreeFormat::Json => INode::from_json(&input.parse()?)?,
+        _ => bail!("Cannot read {:?}", from),
+    };
+    let dir = root
+        .get(path)
+        .with_context(|| format!("No such file or directory: {}", path))?;
+    ensure!(dir.is_dir(), "{} is not a directory", path);
+    Ok(match to {
+        TreeFormat::Transcript => dir.to_transcript(),
+        TreeFormat::Json => dir.to_json().to_string() + "\n",
+        TreeFormat::Tree => dir.tree(human),
+        TreeFormat::Du => dir.du(human),
     })
 }
 
-#[allow(dead_code)]
-fn print_fs(node: &INode, depth: u32) {
-    for _ in 0..depth {
-        print!("  ");
-    }
-    println!("{}", node);
-    if let INodeType::Dir(children) = &node.inode_type {
-        for child in children.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
-            print_fs(child.1, depth + 1);
-        }
-    }
+#[aoc_generator(day7)]
+fn input_generator(input: &str) -> Result<Input> {
+    Ok(replay_transcript(input)?.root)
 }
 
 #[aoc(day7, part1)]
 fn part1(input: &Input) -> Result<Output> {
     // println!("{}", input.size());
-    // print_fs(input, 0);
+    // print!("{}", input.tree(false));
     let result = input
         .apply(|n| {
             if let INodeType::Dir(_) = n.inode_type {
@@ -197,33 +664,16 @@ fn part1(input: &Input) -> Result<Output> {
 
 #[aoc(day7, part2)]
 fn part2(input: &Input) -> Result<Output> {
-    let free_space = 70000000 - input.size();
-    let space_needed = 30000000 - free_space;
-
-    let result = *input
-        .apply(|n| {
-            if let INodeType::Dir(_) = n.inode_type {
-                let size = n.size();
-                if size > space_needed {
-                    // println!("{} -> {}", n, size);
-                    Some(size)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .iter()
-        .min()
-        .unwrap();
-
-    Ok(result)
+    let size = input
+        .smallest_to_free(DISK_SIZE, REQUIRED_SPACE)?
+        .map_or(0, |(_path, size)| size);
+    Ok(size)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::Lcg;
 
     const INPUT_STR: &str = "$ cd /
 $ ls
@@ -262,4 +712,191 @@ $ ls
         assert_eq!(part2(&input)?, 24933642);
         Ok(())
     }
+
+    #[test]
+    fn shell_test() -> Result<()> {
+        let size = |shell: &Shell, path| shell.root.get(path).map(INode::size);
+        let shell = replay_transcript(INPUT_STR)?;
+        assert_eq!(size(&shell, "/a/e"), Some(584));
+        assert_eq!(size(&shell, "/a"), Some(94853));
+        assert_eq!(size(&shell, "/d/k"), Some(7214296));
+        assert_eq!(size(&shell, "/"), Some(48381165));
+        assert_eq!(size(&shell, "/a/x"), None);
+        assert_eq!(shell.cwd_path(), "/d");
+
+        // Jumping back to the root and listing things again changes nothing
+        let repeated = format!(
+            "{}\n$ cd /\n$ cd a\n$ ls\ndir e\n29116 f\n$ cd /\n$ ls\n8504156 c.dat",
+            INPUT_STR
+        );
+        let mut shell = replay_transcript(&repeated)?;
+        assert_eq!(size(&shell, "/"), Some(48381165));
+
+        // New files found later are picked up in cached sizes
+        shell.replay(&"$ ls".parse()?)?;
+        shell.replay(&"100 new".parse()?)?;
+        assert_eq!(size(&shell, "/"), Some(48381265));
+
+        let conflict = format!("{}\n$ cd /\n$ ls\n1 b.txt", INPUT_STR);
+        let err = replay_transcript(&conflict).unwrap_err();
+        assert!(format!("{:#}", err).contains("/b.txt"));
+        let conflict = format!("{}\n$ cd /\n$ ls\n1 a", INPUT_STR);
+        assert!(replay_transcript(&conflict).is_err());
+        assert!(replay_transcript("$ cd /\n1 a").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn report_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        assert_eq!(input.du(true), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+        assert_eq!(
+            input.tree(false),
+            "/ (48381165)
+├── a (94853)
+│   ├── e (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d (24933642)
+    ├── d.ext (5626152)
+    ├── d.log (8033020)
+    ├── j (4060174)
+    └── k (7214296)
+"
+        );
+        assert_eq!(format_size(1536, true), "1.5K");
+
+        assert_eq!(
+            input.smallest_to_free(DISK_SIZE, REQUIRED_SPACE)?,
+            Some(("/d".to_string(), 24933642))
+        );
+        assert_eq!(
+            input.smallest_to_free(50000000, 1700000)?,
+            Some(("/a".to_string(), 94853))
+        );
+        assert!(input.smallest_to_free(50000000, 60000000).is_err());
+        // 70000000 - 48381165 is already free
+        assert_eq!(input.smallest_to_free(DISK_SIZE, 21618835)?, None);
+        assert_eq!(
+            input.smallest_to_free(DISK_SIZE, 21618836)?,
+            Some(("/a/e".to_string(), 584))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn json_test() -> Result<()> {
+        let input = input_generator(INPUT_STR)?;
+        let json = input.to_json().to_string();
+        assert!(json.starts_with(
+            r#"{"name":"/","type":"dir","children":[{"name":"a","type":"dir","children":[{"name":"e""#
+        ));
+
+        let pretty = json.replace(',', ",\n  ").replace(':', " : ");
+        let parsed = INode::from_json(&pretty.parse()?)?;
+        assert_eq!(parsed.to_json().to_string(), json);
+        assert_eq!(parsed.size(), 48381165);
+
+        let replayed = input_generator(&parsed.to_transcript())?;
+        assert_eq!(replayed.to_json().to_string(), json);
+
+        let exported = convert_filesystem(
+            INPUT_STR,
+            TreeFormat::Transcript,
+            TreeFormat::Json,
+            "/",
+            false,
+        )?;
+        assert_eq!(exported, json.clone() + "\n");
+        assert_eq!(
+            convert_filesystem(&exported, "json".parse()?, "du".parse()?, "/", true)?,
+            input.du(true)
+        );
+        assert_eq!(
+            convert_filesystem(
+                INPUT_STR,
+                TreeFormat::Transcript,
+                TreeFormat::Du,
+                "/a",
+                false
+            )?,
+            "94853\t/\n584\t/e\n"
+        );
+        assert!(convert_filesystem(
+            INPUT_STR,
+            TreeFormat::Transcript,
+            TreeFormat::Du,
+            "/b.txt",
+            false
+        )
+        .is_err());
+        assert!(
+            convert_filesystem(&exported, TreeFormat::Tree, TreeFormat::Json, "/", false).is_err()
+        );
+
+        let escaped: Json = r#"["a\"b\\c\ndé", null, true, -3, {}]"#.parse()?;
+        assert_eq!(
+            escaped.to_string(),
+            "[\"a\\\"b\\\\c\\nd\u{e9}\",null,true,-3,{}]"
+        );
+        assert_eq!(escaped.to_string().parse::<Json>()?, escaped);
+
+        assert!("[1,2".parse::<Json>().is_err());
+        assert!("[1.5]".parse::<Json>().is_err());
+        assert!("[] x".parse::<Json>().is_err());
+        assert!(INode::from_json(&r#"{"name":"f","type":"file","size":-1}"#.parse()?).is_err());
+        for bad in ["", "a/b", "a b", "a\\nb", "..", "."] {
+            let json = format!(
+                r#"{{"name":"/","type":"dir","children":[{{"name":"{}","type":"file","size":1}}]}}"#,
+                bad
+            );
+            let err = INode::from_json(&json.parse()?).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Invalid name {:?} in /", bad.replace("\\n", "\n"))
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn transcript_fuzz_test() -> Result<()> {
+        fn random_dir(rng: &mut Lcg, name: &str, depth: u32) -> INode {
+            let mut children = HashMap::new();
+            for idx in 0..rng.next_u31() % 6 {
+                let name = format!("n{}.{}", idx, rng.next_u31() % 3);
+                let child = if depth < 4 && rng.next_u31() % 3 == 1 {
+                    random_dir(rng, &name, depth + 1)
+                } else {
+                    INode {
+                        name: name.clone(),
+                        size_cell: OnceCell::with_value(rng.next_u31() as usize % 100000),
+                        inode_type: INodeType::File,
+                    }
+                };
+                children.insert(name, child);
+            }
+            INode {
+                name: name.to_string(),
+                size_cell: OnceCell::new(),
+                inode_type: INodeType::Dir(children),
+            }
+        }
+
+        let mut rng = Lcg::new(7);
+        for _ in 0..50 {
+            let tree = random_dir(&mut rng, "/", 0);
+            let json = tree.to_json().to_string();
+            let replayed = input_generator(&tree.to_transcript())?;
+            assert_eq!(replayed.to_json().to_string(), json);
+            assert_eq!(replayed.size(), tree.size());
+            let parsed = INode::from_json(&json.parse()?)?;
+            assert_eq!(parsed.to_json().to_string(), json);
+        }
+        Ok(())
+    }
 }
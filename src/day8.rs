@@ -1,4 +1,4 @@
-use anyhow::{ensure, Result};
+use anyhow::{ensure, Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use salusa_aoc::MatrixTranspose;
 
@@ -151,6 +151,71 @@ fn scenic_scores(trees: &Input) -> Result<Vec<Vec<i32>>> {
     Ok(result)
 }
 
+/// For each position in a line of trees, how far you can see back towards the start of the line
+/// and whether you can see all the way to the edge.
+/// Uses a stack of trees which haven't yet been blocked from view, so each tree is pushed and popped at most once.
+fn look_back<F>(len: usize, height: F) -> Vec<(i32, bool)>
+where
+    F: Fn(usize) -> u8,
+{
+    let mut result = Vec::with_capacity(len);
+    let mut stack: Vec<usize> = vec![];
+    for idx in 0..len {
+        let curr_height = height(idx);
+        while stack.last().is_some_and(|top| height(*top) < curr_height) {
+            stack.pop();
+        }
+        result.push(match stack.last() {
+            Some(blocker) => ((idx - blocker) as i32, false),
+            None => (idx as i32, true),
+        });
+        stack.push(idx);
+    }
+    result
+}
+
+type VisibilityAndScores = (Vec<Vec<bool>>, Vec<Vec<i32>>);
+
+/// Computes both the visibility map and scenic scores in a single linear pass in each direction
+fn visibility_and_scores(trees: &Input) -> Result<VisibilityAndScores> {
+    ensure!(!trees.is_empty(), "Must have non-zero width");
+    let width = trees.len();
+    let height = trees[0].len();
+
+    let mut visible = vec![vec![false; height]; width];
+    let mut scores = vec![vec![1; height]; width];
+    let mut record = |x: usize, y: usize, (distance, edge): (i32, bool)| {
+        visible[x][y] |= edge;
+        scores[x][y] *= distance;
+    };
+
+    for (x, col) in trees.iter().enumerate() {
+        for (y, view) in look_back(height, |y| col[y]).into_iter().enumerate() {
+            record(x, y, view);
+        }
+        for (y, view) in look_back(height, |y| col[height - 1 - y])
+            .into_iter()
+            .enumerate()
+        {
+            record(x, height - 1 - y, view);
+        }
+    }
+    #[allow(clippy::needless_range_loop)]
+    for y in 0..height {
+        for (x, view) in look_back(width, |x| trees[x][y]).into_iter().enumerate() {
+            record(x, y, view);
+        }
+        for (x, view) in look_back(width, |x| trees[width - 1 - x][y])
+            .into_iter()
+            .enumerate()
+        {
+            record(width - 1 - x, y, view);
+        }
+    }
+
+    Ok((visible, scores))
+}
+
 #[aoc(day8, part1)]
 fn part1(input: &Input) -> Result<Output> {
     let visibility = visibility_map(input)?;
@@ -175,9 +240,22 @@ fn part2(input: &Input) -> Result<Output> {
     Ok(best_score)
 }
 
+#[aoc(day8, part1, stack)]
+fn part1_stack(input: &Input) -> Result<Output> {
+    let (visibility, _) = visibility_and_scores(input)?;
+    Ok(visibility.iter().flatten().filter(|v| **v).count() as i32)
+}
+
+#[aoc(day8, part2, stack)]
+fn part2_stack(input: &Input) -> Result<Output> {
+    let (_, scores) = visibility_and_scores(input)?;
+    scores.iter().flatten().max().copied().context("No trees")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::Lcg;
 
     const INPUT_STR: &str = "30373
                                 25512
@@ -189,6 +267,7 @@ mod test {
     fn part1_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part1(&input)?, 21);
+        assert_eq!(part1_stack(&input)?, 21);
         Ok(())
     }
 
@@ -196,6 +275,27 @@ mod test {
     fn part2_test() -> Result<()> {
         let input = input_generator(INPUT_STR)?;
         assert_eq!(part2(&input)?, 8);
+        assert_eq!(part2_stack(&input)?, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn random_forest_test() -> Result<()> {
+        let mut rng = Lcg::new(8);
+        for (width, height) in [(1, 1), (1, 7), (6, 1), (2, 2), (13, 9), (40, 40)] {
+            for _ in 0..10 {
+                let forest: Input = (0..width)
+                    .map(|_| {
+                        (0..height)
+                            .map(|_| b'0' + (rng.next_u31() % 10) as u8)
+                            .collect()
+                    })
+                    .collect();
+                let (visibility, scores) = visibility_and_scores(&forest)?;
+                assert_eq!(visibility, visibility_map(&forest)?);
+                assert_eq!(scores, scenic_scores(&forest)?);
+            }
+        }
         Ok(())
     }
 }
@@ -4,26 +4,23 @@ use anyhow::{Context, Result};
 use aoc_runner_derive::{aoc, aoc_generator};
 use strum::EnumString;
 
+use crate::grid::{self, Point, Point2};
+
 type Input = Vec<Command>;
 type Output = usize;
 
-type Coord = (i32, i32);
-
-fn offset(head: &Coord, tail: &Coord) -> Coord {
-    (head.0 - tail.0, head.1 - tail.1)
-}
+type Coord = Point2<i32>;
 
 fn adjacent(head: &Coord, tail: &Coord) -> bool {
-    let offset = offset(head, tail);
-    offset.0.abs() <= 1 && offset.1.abs() <= 1
+    head.chebyshev(tail) <= 1
 }
 
-fn step(coord: &Coord, dir: Direction) -> Coord {
-    match dir {
-        Direction::U => (coord.0, coord.1 + 1),
-        Direction::D => (coord.0, coord.1 - 1),
-        Direction::L => (coord.0 - 1, coord.1),
-        Direction::R => (coord.0 + 1, coord.1),
+/// Moves `tail` one step (possibly diagonally) towards `head` if they are no longer touching
+fn follow(head: &Coord, tail: &Coord) -> Coord {
+    if adjacent(head, tail) {
+        *tail
+    } else {
+        *tail + (*head - *tail).signum()
     }
 }
 
@@ -35,6 +32,17 @@ enum Direction {
     R,
 }
 
+impl From<Direction> for grid::Direction {
+    fn from(value: Direction) -> Self {
+        match value {
+            Direction::U => grid::Direction::Up,
+            Direction::D => grid::Direction::Down,
+            Direction::L => grid::Direction::Left,
+            Direction::R => grid::Direction::Right,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Command {
     dist: u32,
@@ -63,60 +71,33 @@ fn input_generator(input: &str) -> Result<Input> {
 }
 
 #[aoc(day9, part1)]
-#[allow(clippy::comparison_chain)]
 fn part1(input: &Input) -> Result<Output> {
-    let mut head = (0, 0);
-    let mut tail = (0, 0);
+    let mut head = Point([0, 0]);
+    let mut tail = Point([0, 0]);
     let mut visited: HashSet<Coord> = HashSet::new();
     visited.insert(tail);
 
     for cmd in input {
         for _ in 0..cmd.dist {
-            head = step(&head, cmd.dir);
-            if !adjacent(&head, &tail) {
-                let offset = offset(&head, &tail);
-                if offset.0 < 0 {
-                    tail = step(&tail, Direction::L);
-                } else if offset.0 > 0 {
-                    tail = step(&tail, Direction::R);
-                }
-                if offset.1 < 0 {
-                    tail = step(&tail, Direction::D);
-                } else if offset.1 > 0 {
-                    tail = step(&tail, Direction::U);
-                }
-
-                visited.insert(tail);
-            }
+            head = grid::Direction::from(cmd.dir).step(&head);
+            tail = follow(&head, &tail);
+            visited.insert(tail);
         }
     }
     Ok(visited.len())
 }
 
 #[aoc(day9, part2)]
-#[allow(clippy::comparison_chain)]
 fn part2(input: &Input) -> Result<Output> {
-    let mut rope = vec![(0, 0); 10];
+    let mut rope = vec![Point([0, 0]); 10];
     let mut visited: HashSet<Coord> = HashSet::new();
-    visited.insert((0, 0));
+    visited.insert(Point([0, 0]));
 
     for cmd in input {
         for _ in 0..cmd.dist {
-            rope[0] = step(&rope[0], cmd.dir);
+            rope[0] = grid::Direction::from(cmd.dir).step(&rope[0]);
             for idx in 1..rope.len() {
-                if !adjacent(&rope[idx - 1], &rope[idx]) {
-                    let offset = offset(&rope[idx - 1], &rope[idx]);
-                    if offset.0 < 0 {
-                        rope[idx] = step(&rope[idx], Direction::L);
-                    } else if offset.0 > 0 {
-                        rope[idx] = step(&rope[idx], Direction::R);
-                    }
-                    if offset.1 < 0 {
-                        rope[idx] = step(&rope[idx], Direction::D);
-                    } else if offset.1 > 0 {
-                        rope[idx] = step(&rope[idx], Direction::U);
-                    }
-                }
+                rope[idx] = follow(&rope[idx - 1], &rope[idx]);
             }
             visited.insert(rope[9]);
         }
@@ -0,0 +1,630 @@
+//! Points, directions and grids shared by the days which walk around maps.
+//!
+//! Two dimensional code follows the puzzle text: `x` grows to the right and `y` grows downwards.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display, Write},
+    hash::Hash,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+};
+
+use anyhow::{bail, Result};
+
+/// The integer types which can be used as coordinates
+pub trait Number:
+    Copy
+    + Ord
+    + Default
+    + Hash
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+    + From<i8>
+{
+}
+
+impl<T> Number for T where
+    T: Copy
+        + Ord
+        + Default
+        + Hash
+        + Debug
+        + Add<Output = T>
+        + Sub<Output = T>
+        + Mul<Output = T>
+        + Neg<Output = T>
+        + From<i8>
+{
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point<T, const D: usize>(pub [T; D]);
+
+pub type Point2<T> = Point<T, 2>;
+pub type Point3<T> = Point<T, 3>;
+
+impl<T: Number, const D: usize> Default for Point<T, D> {
+    fn default() -> Self {
+        Self([T::default(); D])
+    }
+}
+
+impl<T: Number, const D: usize> Point<T, D> {
+    fn zip_with(self, other: Self, f: impl Fn(T, T) -> T) -> Self {
+        let mut result = self;
+        for axis in 0..D {
+            result.0[axis] = f(self.0[axis], other.0[axis]);
+        }
+        result
+    }
+
+    fn axis_distances(&self, other: &Self) -> impl Iterator<Item = T> + '_ {
+        let other = *other;
+        (0..D).map(move |axis| self.0[axis].max(other.0[axis]) - self.0[axis].min(other.0[axis]))
+    }
+
+    /// Distance moving along one axis at a time
+    pub fn manhattan(&self, other: &Self) -> T {
+        self.axis_distances(other)
+            .fold(T::default(), |acc, d| acc + d)
+    }
+
+    /// Distance moving diagonally as well, so the number of king moves in two dimensions
+    pub fn chebyshev(&self, other: &Self) -> T {
+        self.axis_distances(other)
+            .fold(T::default(), |acc, d| acc.max(d))
+    }
+
+    /// Each coordinate clamped to -1, 0 or 1
+    pub fn signum(&self) -> Self {
+        let zero = T::default();
+        let mut result = *self;
+        for c in result.0.iter_mut() {
+            *c = match (*c).cmp(&zero) {
+                std::cmp::Ordering::Less => T::from(-1),
+                std::cmp::Ordering::Equal => zero,
+                std::cmp::Ordering::Greater => T::from(1),
+            };
+        }
+        result
+    }
+
+    /// The `2 * D` points one step away along a single axis
+    pub fn orthogonal(&self) -> impl Iterator<Item = Self> + '_ {
+        (0..2 * D).map(move |idx| {
+            let mut result = *self;
+            let step = if idx % 2 == 0 { -1 } else { 1 };
+            result.0[idx / 2] = result.0[idx / 2] + T::from(step);
+            result
+        })
+    }
+
+    /// The `3^D - 1` points touching this one, including diagonally, in lexicographic order
+    pub fn adjacent(&self) -> impl Iterator<Item = Self> + '_ {
+        let count = 3usize.pow(D as u32);
+        (0..count)
+            .filter(move |idx| *idx != count / 2)
+            .map(move |idx| {
+                let mut result = *self;
+                let mut idx = idx;
+                for axis in (0..D).rev() {
+                    result.0[axis] = result.0[axis] + T::from((idx % 3) as i8 - 1);
+                    idx /= 3;
+                }
+                result
+            })
+    }
+}
+
+impl<T: Copy> Point<T, 2> {
+    pub fn x(&self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> T {
+        self.0[1]
+    }
+}
+
+impl<T: Copy> Point<T, 3> {
+    pub fn x(&self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> T {
+        self.0[1]
+    }
+
+    pub fn z(&self) -> T {
+        self.0[2]
+    }
+}
+
+impl<T, const D: usize> Index<usize> for Point<T, D> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T, const D: usize> IndexMut<usize> for Point<T, D> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl<T: Number, const D: usize> Add for Point<T, D> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+}
+
+impl<T: Number, const D: usize> Sub for Point<T, D> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+}
+
+impl<T: Number, const D: usize> Mul<T> for Point<T, D> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        self.zip_with(self, |a, _| a * rhs)
+    }
+}
+
+impl<T: Number, const D: usize> Neg for Point<T, D> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.zip_with(self, |a, _| -a)
+    }
+}
+
+impl<T: Number, const D: usize> AddAssign for Point<T, D> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Number, const D: usize> SubAssign for Point<T, D> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T> From<(T, T)> for Point<T, 2> {
+    fn from(value: (T, T)) -> Self {
+        Self([value.0, value.1])
+    }
+}
+
+impl<T> From<(T, T, T)> for Point<T, 3> {
+    fn from(value: (T, T, T)) -> Self {
+        Self([value.0, value.1, value.2])
+    }
+}
+
+impl<T: Display, const D: usize> Display for Point<T, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (idx, c) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// The four orthogonal directions, clockwise from `Up`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn offset<T: Number>(&self) -> Point2<T> {
+        let (x, y) = match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        };
+        Point([T::from(x), T::from(y)])
+    }
+
+    pub fn step<T: Number>(&self, from: &Point2<T>) -> Point2<T> {
+        *from + self.offset()
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        Direction::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        Direction::ALL[(*self as usize + 3) % 4]
+    }
+}
+
+/// The eight compass directions, clockwise from `N`, with north being up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compass {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Compass {
+    pub const ALL: [Compass; 8] = [
+        Compass::N,
+        Compass::NE,
+        Compass::E,
+        Compass::SE,
+        Compass::S,
+        Compass::SW,
+        Compass::W,
+        Compass::NW,
+    ];
+
+    /// The compass points matching `Direction::ALL`
+    pub const ORTHOGONAL: [Compass; 4] = [Compass::N, Compass::E, Compass::S, Compass::W];
+
+    pub fn is_diagonal(&self) -> bool {
+        matches!(self, Compass::NE | Compass::SE | Compass::SW | Compass::NW)
+    }
+
+    pub fn offset<T: Number>(&self) -> Point2<T> {
+        let (x, y) = match self {
+            Compass::N => (0, -1),
+            Compass::NE => (1, -1),
+            Compass::E => (1, 0),
+            Compass::SE => (1, 1),
+            Compass::S => (0, 1),
+            Compass::SW => (-1, 1),
+            Compass::W => (-1, 0),
+            Compass::NW => (-1, -1),
+        };
+        Point([T::from(x), T::from(y)])
+    }
+
+    pub fn step<T: Number>(&self, from: &Point2<T>) -> Point2<T> {
+        *from + self.offset()
+    }
+}
+
+impl From<Direction> for Compass {
+    fn from(value: Direction) -> Self {
+        match value {
+            Direction::Up => Compass::N,
+            Direction::Right => Compass::E,
+            Direction::Down => Compass::S,
+            Direction::Left => Compass::W,
+        }
+    }
+}
+
+/// The smallest box, inclusive at both ends, containing a set of points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox<T, const D: usize> {
+    pub min: Point<T, D>,
+    pub max: Point<T, D>,
+}
+
+impl<T: Number, const D: usize> BoundingBox<T, D> {
+    pub fn new(point: Point<T, D>) -> Self {
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// `None` if there are no points
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point<T, D>>) -> Option<Self>
+    where
+        T: 'a,
+    {
+        let mut points = points.into_iter();
+        let mut result = Self::new(*points.next()?);
+        for p in points {
+            result.include(p);
+        }
+        Some(result)
+    }
+
+    pub fn include(&mut self, point: &Point<T, D>) {
+        self.min = self.min.zip_with(*point, T::min);
+        self.max = self.max.zip_with(*point, T::max);
+    }
+
+    pub fn contains(&self, point: &Point<T, D>) -> bool {
+        (0..D).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    /// Number of points along each axis
+    pub fn size(&self) -> Point<T, D> {
+        self.max - self.min + Point([T::from(1); D])
+    }
+}
+
+impl BoundingBox<i32, 2> {
+    /// One line per row from top to bottom, built from `f` for each point in the box
+    pub fn render<S: Display>(&self, mut f: impl FnMut(Point2<i32>) -> S) -> String {
+        let mut result = String::new();
+        for y in self.min.y()..=self.max.y() {
+            for x in self.min.x()..=self.max.x() {
+                write!(result, "{}", f(Point([x, y]))).unwrap();
+            }
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/// A two dimensional map of cells, either stored densely from `(0, 0)` or sparsely as only the interesting points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Grid<T> {
+    Dense {
+        width: usize,
+        height: usize,
+        cells: Vec<Option<T>>,
+    },
+    Sparse(HashMap<Point2<i32>, T>),
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Grid::Sparse(HashMap::new())
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parses a rectangular block of text where every character is a cell
+    pub fn parse_dense(
+        input: &str,
+        mut f: impl FnMut(Point2<i32>, char) -> Result<T>,
+    ) -> Result<Self> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+        for (y, line) in input.lines().enumerate() {
+            let start = cells.len();
+            for (x, c) in line.chars().enumerate() {
+                cells.push(Some(f(Point([x as i32, y as i32]), c)?));
+            }
+            let len = cells.len() - start;
+            match width {
+                None => width = Some(len),
+                Some(width) if width != len => {
+                    bail!("Line {} has {} cells, expected {}", y + 1, len, width)
+                }
+                _ => {}
+            }
+            height += 1;
+        }
+        Ok(Grid::Dense {
+            width: width.unwrap_or_default(),
+            height,
+            cells,
+        })
+    }
+
+    /// Parses text where only the characters which `f` maps to `Some` are kept
+    pub fn parse_sparse(
+        input: &str,
+        mut f: impl FnMut(Point2<i32>, char) -> Result<Option<T>>,
+    ) -> Result<Self> {
+        let mut cells = HashMap::new();
+        for (y, line) in input.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let point = Point([x as i32, y as i32]);
+                if let Some(cell) = f(point, c)? {
+                    cells.insert(point, cell);
+                }
+            }
+        }
+        Ok(Grid::Sparse(cells))
+    }
+
+    fn dense_index(&self, point: &Point2<i32>) -> Option<usize> {
+        match self {
+            Grid::Dense { width, height, .. } => {
+                let (x, y) = (
+                    usize::try_from(point.x()).ok()?,
+                    usize::try_from(point.y()).ok()?,
+                );
+                (x < *width && y < *height).then_some(y * width + x)
+            }
+            Grid::Sparse(_) => None,
+        }
+    }
+
+    pub fn get(&self, point: &Point2<i32>) -> Option<&T> {
+        match self {
+            Grid::Dense { cells, .. } => cells.get(self.dense_index(point)?)?.as_ref(),
+            Grid::Sparse(cells) => cells.get(point),
+        }
+    }
+
+    pub fn contains(&self, point: &Point2<i32>) -> bool {
+        self.get(point).is_some()
+    }
+
+    /// Stores `value` at `point`, returning what was there before.
+    /// Panics if a dense grid doesn't reach that far.
+    pub fn insert(&mut self, point: Point2<i32>, value: T) -> Option<T> {
+        let idx = self.dense_index(&point);
+        match self {
+            Grid::Dense { cells, .. } => {
+                let idx = idx.unwrap_or_else(|| panic!("{} is outside the grid", point));
+                cells[idx].replace(value)
+            }
+            Grid::Sparse(cells) => cells.insert(point, value),
+        }
+    }
+
+    pub fn remove(&mut self, point: &Point2<i32>) -> Option<T> {
+        let idx = self.dense_index(point);
+        match self {
+            Grid::Dense { cells, .. } => cells.get_mut(idx?)?.take(),
+            Grid::Sparse(cells) => cells.remove(point),
+        }
+    }
+
+    /// Number of points holding a cell
+    pub fn len(&self) -> usize {
+        match self {
+            Grid::Dense { cells, .. } => cells.iter().flatten().count(),
+            Grid::Sparse(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (Point2<i32>, &T)> + '_> {
+        match self {
+            Grid::Dense { width, cells, .. } => {
+                let width = *width;
+                Box::new(cells.iter().enumerate().filter_map(move |(idx, c)| {
+                    let point = Point([(idx % width) as i32, (idx / width) as i32]);
+                    c.as_ref().map(|c| (point, c))
+                }))
+            }
+            Grid::Sparse(cells) => Box::new(cells.iter().map(|(p, c)| (*p, c))),
+        }
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox<i32, 2>> {
+        match self {
+            Grid::Dense { width, height, .. } if *width > 0 && *height > 0 => Some(BoundingBox {
+                min: Point([0, 0]),
+                max: Point([*width as i32 - 1, *height as i32 - 1]),
+            }),
+            Grid::Dense { .. } => None,
+            Grid::Sparse(cells) => BoundingBox::from_points(cells.keys()),
+        }
+    }
+
+    /// Renders every point in the bounding box, with `f` given `None` for points without a cell
+    pub fn render<S: Display>(&self, mut f: impl FnMut(Option<&T>) -> S) -> String {
+        match self.bounding_box() {
+            Some(bounds) => bounds.render(|p| f(self.get(&p))),
+            None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn point_test() {
+        let a = Point([1, -2]);
+        let b = Point([4, 2]);
+        assert_eq!(a + b, Point([5, 0]));
+        assert_eq!(b - a, Point([3, 4]));
+        assert_eq!(a.manhattan(&b), 7);
+        assert_eq!(a.chebyshev(&b), 4);
+        assert_eq!((a - b).signum(), Point([-1, -1]));
+        assert_eq!(a * 3, Point([3, -6]));
+
+        let p: Point3<i64> = (0, 0, 0).into();
+        assert_eq!(p.orthogonal().count(), 6);
+        assert_eq!(p.adjacent().count(), 26);
+        assert!(p.orthogonal().all(|n| n.manhattan(&p) == 1));
+        assert!(p.adjacent().all(|n| n.chebyshev(&p) == 1));
+        assert_eq!(
+            Point([0, 0]).adjacent().take(3).collect::<Vec<_>>(),
+            vec![Point([-1, -1]), Point([-1, 0]), Point([-1, 1])]
+        );
+    }
+
+    #[test]
+    fn direction_test() {
+        for d in Direction::ALL {
+            assert_eq!(d.turn_left().turn_right(), d);
+            assert_eq!(Compass::from(d).offset::<i32>(), d.offset());
+        }
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.step(&Point([3, 3])), Point([3, 2]));
+        let around: Vec<Point2<i32>> = Compass::ALL.iter().map(|c| c.offset()).collect();
+        assert_eq!(around.len(), 8);
+        assert_eq!(
+            Compass::ORTHOGONAL.map(|c| c.offset::<i32>()),
+            Direction::ALL.map(|d| d.offset())
+        );
+        assert_eq!(Compass::ALL.iter().filter(|c| c.is_diagonal()).count(), 4);
+        assert!(Point([0, 0]).adjacent().all(|p| around.contains(&p)));
+    }
+
+    #[test]
+    fn grid_test() -> Result<()> {
+        let text = "#..\n.#.\n..#";
+        let mut dense = Grid::parse_dense(text, |_, c| Ok(c == '#'))?;
+        assert_eq!(dense.len(), 9);
+        assert_eq!(dense.get(&Point([1, 1])), Some(&true));
+        assert_eq!(dense.get(&Point([2, 1])), Some(&false));
+        assert_eq!(dense.get(&Point([3, 1])), None);
+        assert_eq!(
+            dense.render(|c| if *c.unwrap() { '#' } else { '.' }),
+            text.to_string() + "\n"
+        );
+        assert!(Grid::parse_dense("..\n...", |_, c| Ok(c)).is_err());
+        assert_eq!(dense.insert(Point([2, 0]), true), Some(false));
+        assert_eq!(dense.remove(&Point([0, 0])), Some(true));
+        assert!(!dense.contains(&Point([0, 0])));
+        assert_eq!(dense.len(), 8);
+        assert_eq!(dense.iter().filter(|(_, c)| **c).count(), 3);
+        assert_eq!(
+            dense.render(|c| c.map_or(' ', |c| if *c { '#' } else { '.' })),
+            " .#\n.#.\n..#\n"
+        );
+
+        let mut sparse = Grid::parse_sparse(text, |_, c| Ok((c == '#').then_some(())))?;
+        assert_eq!(sparse.len(), 3);
+        assert_eq!(sparse.insert(Point([-1, 4]), ()), None);
+        assert_eq!(
+            sparse.bounding_box(),
+            Some(BoundingBox {
+                min: Point([-1, 0]),
+                max: Point([2, 4])
+            })
+        );
+        assert_eq!(
+            sparse.render(|c| if c.is_some() { '#' } else { '.' }),
+            ".#..\n..#.\n...#\n....\n#...\n"
+        );
+        assert_eq!(sparse.remove(&Point([-1, 4])), Some(()));
+        assert_eq!(sparse.remove(&Point([-1, 4])), None);
+        assert!(Grid::<()>::default().bounding_box().is_none());
+        assert_eq!(Grid::<()>::default().render(|_| '#'), "");
+        Ok(())
+    }
+}
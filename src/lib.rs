@@ -23,20 +23,15 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
-
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
-
+mod grid;
 #[cfg(test)]
-mod tests {
-    use super::*;
+mod test_util;
+pub mod bench;
+pub mod registry;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use day2::search_decoders;
+pub use day7::{convert_filesystem, TreeFormat};
+pub use day12::render_route;
+pub use day18::{describe_cavities, write_mesh, MeshFormat};
 
 aoc_lib! { year = 2022 }
@@ -0,0 +1,436 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+    process::ExitCode,
+};
+
+use anyhow::{bail, Context, Result};
+use aoc_2022::{
+    bench::{self, Measurement},
+    registry::{self, Solution},
+    MeshFormat, TreeFormat,
+};
+use itertools::Itertools;
+
+const USAGE: &str = "Usage:
+    aoc_2022 list [DAY]
+    aoc_2022 run DAY [PART] [--variant NAME] [--input FILE|-]
+    aoc_2022 bench [DAY [PART]] [--variant NAME] [--runs N] [--csv FILE] [--baseline FILE] [--threshold PERCENT]
+    aoc_2022 decoder [--worst] [--input FILE|-]
+    aoc_2022 filesystem [--from transcript|json] [--to transcript|json|tree|du] [--path DIR] [--human] [--input FILE|-]
+    aoc_2022 route [--plain] [--input FILE|-]
+    aoc_2022 cavities [--diagonal] [--input FILE|-]
+    aoc_2022 mesh OUTPUT [--exterior] [--format obj|stl] [--input FILE|-]
+
+The input defaults to input/2022/dayN.txt, and '-' reads it from stdin.
+Benchmarking every day skips those without an input, and is only meaningful in release builds.
+The decoder lists every day 2 reading of X, Y and Z with its score, marking the best (or worst).
+The filesystem is the day 7 tree, read as a transcript and written as a tree listing from / by default.
+The route is a shortest day 12 path, coloured by height unless --plain is used.
+The cavities are the day 18 air pockets, which --diagonal lets air leave through edges and corners.
+The mesh is the day 18 droplet, in the format given by the OUTPUT extension unless --format is used.";
+
+#[derive(Debug, Default)]
+struct RunArgs {
+    day: u32,
+    part: Option<u32>,
+    variant: Option<String>,
+    input: Option<String>,
+}
+
+#[derive(Debug)]
+struct BenchArgs {
+    day: Option<u32>,
+    part: Option<u32>,
+    variant: Option<String>,
+    runs: usize,
+    csv: Option<String>,
+    baseline: Option<String>,
+    threshold: f64,
+}
+
+fn parse_day_part(positional: &[&String]) -> Result<(Option<u32>, Option<u32>)> {
+    match positional {
+        [] => Ok((None, None)),
+        [day] => Ok((Some(day.parse().context("Bad day")?), None)),
+        [day, part] => Ok((
+            Some(day.parse().context("Bad day")?),
+            Some(part.parse().context("Bad part")?),
+        )),
+        _ => bail!("Expected DAY [PART]"),
+    }
+}
+
+fn parse_run_args(args: &[String]) -> Result<RunArgs> {
+    let mut result = RunArgs::default();
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--variant" => {
+                result.variant = Some(args.next().context("--variant needs a name")?.clone())
+            }
+            "--input" => result.input = Some(args.next().context("--input needs a file")?.clone()),
+            _ => positional.push(arg),
+        }
+    }
+    let (day, part) = parse_day_part(&positional)?;
+    result.day = day.context("Expected DAY [PART]")?;
+    result.part = part;
+    Ok(result)
+}
+
+fn parse_bench_args(args: &[String]) -> Result<BenchArgs> {
+    let mut result = BenchArgs {
+        day: None,
+        part: None,
+        variant: None,
+        runs: 10,
+        csv: None,
+        baseline: None,
+        threshold: 0.1,
+    };
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--variant" => {
+                result.variant = Some(args.next().context("--variant needs a name")?.clone())
+            }
+            "--runs" => {
+                result.runs = args
+                    .next()
+                    .context("--runs needs a count")?
+                    .parse()
+                    .context("Bad run count")?
+            }
+            "--csv" => result.csv = Some(args.next().context("--csv needs a file")?.clone()),
+            "--baseline" => {
+                result.baseline = Some(args.next().context("--baseline needs a file")?.clone())
+            }
+            "--threshold" => {
+                let percent: f64 = args
+                    .next()
+                    .context("--threshold needs a percentage")?
+                    .parse()
+                    .context("Bad threshold")?;
+                result.threshold = percent / 100.0;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    (result.day, result.part) = parse_day_part(&positional)?;
+    Ok(result)
+}
+
+fn matches_variant(solution: &Solution, variant: &Option<String>) -> bool {
+    match variant {
+        Some(variant) => solution.variant().unwrap_or("default") == variant,
+        None => true,
+    }
+}
+
+fn label(solution: &Solution) -> String {
+    match solution.variant() {
+        Some(variant) => format!(
+            "Day {} - Part {} - {}",
+            solution.day(),
+            solution.part(),
+            variant
+        ),
+        None => format!("Day {} - Part {}", solution.day(), solution.part()),
+    }
+}
+
+fn list(day: Option<u32>) {
+    let days = match day {
+        Some(day) => vec![day],
+        None => registry::days(),
+    };
+    for day in days {
+        for (part, solutions) in &registry::find(day, None).group_by(|s| s.part()) {
+            let variants = solutions
+                .map(|s| s.variant().unwrap_or("default"))
+                .join(", ");
+            println!("Day {} - Part {}: {}", day, part, variants);
+        }
+    }
+}
+
+fn read_input(day: u32, input: Option<&str>) -> Result<String> {
+    match input {
+        None => registry::default_input(day),
+        Some("-") => {
+            let mut result = String::new();
+            io::stdin().read_to_string(&mut result)?;
+            Ok(result)
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("Could not read {}", path))
+        }
+    }
+}
+
+/// Runs everything selected, returning false if any solution fails or variants of a part disagree
+fn run(args: RunArgs) -> Result<bool> {
+    let solutions = registry::find(args.day, args.part)
+        .filter(|s| matches_variant(s, &args.variant))
+        .collect_vec();
+    if solutions.is_empty() {
+        bail!("No solutions match");
+    }
+    let input = read_input(args.day, args.input.as_deref())?;
+
+    let mut ok = true;
+    for (part, solutions) in &solutions.into_iter().group_by(|s| s.part()) {
+        let mut answers = vec![];
+        for solution in solutions {
+            match solution.run(&input) {
+                Ok(outcome) => {
+                    println!(
+                        "{}: {}\n    generator: {:?}, runner: {:?}",
+                        label(solution),
+                        outcome.answer,
+                        outcome.generator,
+                        outcome.runner
+                    );
+                    answers.push((solution, outcome.answer));
+                }
+                Err(e) => {
+                    ok = false;
+                    println!("{}: {:#}", label(solution), e);
+                }
+            }
+        }
+        if !answers.iter().map(|(_, answer)| answer).all_equal() {
+            ok = false;
+            println!("Day {} - Part {}: variants disagree", args.day, part);
+            for (solution, answer) in answers {
+                println!(
+                    "    {}: {}",
+                    solution.variant().unwrap_or("default"),
+                    answer
+                );
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// Benchmarks everything selected, returning false if anything regressed against the baseline
+fn bench(args: BenchArgs) -> Result<bool> {
+    let days = match args.day {
+        Some(day) => vec![day],
+        None => registry::days()
+            .into_iter()
+            .filter(|&day| registry::input_path(day).exists())
+            .collect(),
+    };
+
+    let mut measurements: Vec<Measurement> = vec![];
+    for day in days {
+        let input = registry::default_input(day)?;
+        for solution in registry::find(day, args.part).filter(|s| matches_variant(s, &args.variant))
+        {
+            let measurement = bench::measure(solution, &input, args.runs)?;
+            println!("{}", measurement);
+            measurements.push(measurement);
+        }
+    }
+
+    if let Some(path) = &args.csv {
+        std::fs::write(path, bench::to_csv(&measurements))
+            .with_context(|| format!("Could not write {}", path))?;
+    }
+
+    let mut ok = true;
+    if let Some(path) = &args.baseline {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Could not read {}", path))?;
+        let baseline = bench::from_csv(&text).with_context(|| format!("Bad baseline {}", path))?;
+        println!("Compared with {}:", path);
+        for comparison in bench::compare(&measurements, &baseline, args.threshold) {
+            ok &= !comparison.regressed;
+            println!("    {}", comparison);
+        }
+    }
+    Ok(ok)
+}
+
+/// Prints every day 2 decoder with its score, marking the best or worst
+fn decoder(args: &[String]) -> Result<()> {
+    let mut worst = false;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--worst" => worst = true,
+            "--input" => input = Some(args.next().context("--input needs a file")?.as_str()),
+            _ => bail!("Unexpected argument {}", arg),
+        }
+    }
+    let input = read_input(2, input)?;
+    print!("{}", aoc_2022::search_decoders(&input, worst)?);
+    Ok(())
+}
+
+/// Converts the day 7 filesystem between formats
+fn filesystem(args: &[String]) -> Result<()> {
+    let mut from = TreeFormat::Transcript;
+    let mut to = TreeFormat::Tree;
+    let mut path = "/";
+    let mut human = false;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let name = args
+                    .next()
+                    .with_context(|| format!("{} needs a format", arg))?;
+                let format = name
+                    .parse()
+                    .with_context(|| format!("Bad format {}", name))?;
+                if arg == "--from" {
+                    from = format;
+                } else {
+                    to = format;
+                }
+            }
+            "--path" => path = args.next().context("--path needs a directory")?,
+            "--human" => human = true,
+            "--input" => input = Some(args.next().context("--input needs a file")?.as_str()),
+            _ => bail!("Unexpected argument {}", arg),
+        }
+    }
+    let input = read_input(7, input)?;
+    print!(
+        "{}",
+        aoc_2022::convert_filesystem(&input, from, to, path, human)?
+    );
+    Ok(())
+}
+
+/// Prints a shortest day 12 route over the height map
+fn route(args: &[String]) -> Result<()> {
+    let mut colour = true;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plain" => colour = false,
+            "--input" => input = Some(args.next().context("--input needs a file")?.as_str()),
+            _ => bail!("Unexpected argument {}", arg),
+        }
+    }
+    let input = read_input(12, input)?;
+    print!("{}", aoc_2022::render_route(&input, colour)?);
+    Ok(())
+}
+
+/// Lists the air pockets trapped inside the day 18 droplet
+fn cavities(args: &[String]) -> Result<()> {
+    let mut diagonal = false;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--diagonal" => diagonal = true,
+            "--input" => input = Some(args.next().context("--input needs a file")?.as_str()),
+            _ => bail!("Unexpected argument {}", arg),
+        }
+    }
+    let input = read_input(18, input)?;
+    print!("{}", aoc_2022::describe_cavities(&input, diagonal)?);
+    Ok(())
+}
+
+/// Writes the day 18 droplet as a 3D model
+fn mesh(args: &[String]) -> Result<()> {
+    let mut output = None;
+    let mut exterior_only = false;
+    let mut format = None;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exterior" => exterior_only = true,
+            "--format" => {
+                let name = args.next().context("--format needs obj or stl")?;
+                format = Some(
+                    name.parse()
+                        .with_context(|| format!("Bad format {}", name))?,
+                );
+            }
+            "--input" => input = Some(args.next().context("--input needs a file")?.as_str()),
+            _ if output.is_none() => output = Some(Path::new(arg)),
+            _ => bail!("Unexpected argument {}", arg),
+        }
+    }
+    let output = output.context("Expected OUTPUT")?;
+    let format: MeshFormat = match format {
+        Some(format) => format,
+        None => output
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| e.parse().ok())
+            .context("Use --format or an .obj or .stl OUTPUT")?,
+    };
+    let input = read_input(18, input)?;
+    aoc_2022::write_mesh(&input, output, exterior_only, format)
+}
+
+fn main() -> Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let day = args
+                .get(1)
+                .map(|d| d.parse())
+                .transpose()
+                .context("Bad day")?;
+            list(day);
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("run") => {
+            let ok = run(parse_run_args(&args[1..])?)?;
+            Ok(if ok {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Some("bench") => {
+            let ok = bench(parse_bench_args(&args[1..])?)?;
+            Ok(if ok {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Some("decoder") => {
+            decoder(&args[1..])?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("filesystem") => {
+            filesystem(&args[1..])?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("route") => {
+            route(&args[1..])?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("cavities") => {
+            cavities(&args[1..])?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some("mesh") => {
+            mesh(&args[1..])?;
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(ExitCode::from(2))
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Every solution registered with `#[aoc]`, so that they can be listed and run without `cargo aoc`.
+//!
+//! `aoc_lib!` only exposes a factory method per solution, so new days and variants need adding to `SOLUTIONS`.
+
+use std::{
+    error::Error,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use aoc_runner::{ArcStr, Runner};
+
+use crate::*;
+
+type FactoryFn = fn(ArcStr) -> Result<Box<dyn Runner>, Box<dyn Error>>;
+
+#[derive(Clone, Copy)]
+pub struct Solution {
+    /// The factory name, such as `day14_part1_fast`
+    name: &'static str,
+    factory: FactoryFn,
+}
+
+/// The answer from a single run along with how long each half took
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub answer: String,
+    pub generator: Duration,
+    pub runner: Duration,
+}
+
+macro_rules! solutions {
+    ($($name:ident),* $(,)?) => {
+        pub const SOLUTIONS: &[Solution] = &[
+            $(Solution { name: stringify!($name), factory: Factory::$name },)*
+        ];
+    };
+}
+
+solutions![
+    day1_part1,
+    day1_part1_streaming,
+    day1_part2,
+    day1_part2_streaming,
+    day2_part1,
+    day2_part2,
+    day3_part1,
+    day3_part2,
+    day4_part1,
+    day4_part1_cover,
+    day4_part2,
+    day4_part2_cover,
+    day5_part1,
+    day5_part1_capped,
+    day5_part2,
+    day5_part2_capped,
+    day6_part1,
+    day6_part1_streaming,
+    day6_part2,
+    day6_part2_streaming,
+    day7_part1,
+    day7_part2,
+    day8_part1,
+    day8_part1_stack,
+    day8_part2,
+    day8_part2_stack,
+    day9_part1,
+    day9_part2,
+    day10_part1,
+    day10_part2,
+    day11_part1,
+    day11_part2,
+    day11_part2_heap,
+    day12_part1,
+    day12_part1_astar,
+    day12_part1_provided,
+    day12_part2,
+    day12_part2_dijkstra,
+    day12_part2_provided,
+    day13_part1,
+    day13_part1_explained,
+    day13_part2,
+    day13_part2_streaming,
+    day13_part2_unsorted,
+    day14_part1,
+    day14_part1_fast,
+    day14_part2,
+    day14_part2_fast,
+    day15_part1,
+    day15_part2,
+    day16_part1,
+    day16_part2,
+    day17_part1,
+    day17_part2,
+    day18_part1,
+    day18_part2,
+    day18_part2_components,
+    day19_part1,
+    day19_part2,
+    day20_part1,
+    day20_part2,
+    day21_part1,
+    day21_part2,
+    day22_part1,
+    day22_part2,
+    day23_part1,
+    day23_part1_bits,
+    day23_part2,
+    day23_part2_bits,
+];
+
+impl Solution {
+    fn split_name(&self) -> (&'static str, &'static str, Option<&'static str>) {
+        let (day, rest) = self.name["day".len()..]
+            .split_once("_part")
+            .expect("Solution names look like dayN_partM");
+        match rest.split_once('_') {
+            Some((part, variant)) => (day, part, Some(variant)),
+            None => (day, rest, None),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn day(&self) -> u32 {
+        self.split_name().0.parse().expect("Numeric day")
+    }
+
+    pub fn part(&self) -> u32 {
+        self.split_name().1.parse().expect("Numeric part")
+    }
+
+    /// `None` for the main solution of a part
+    pub fn variant(&self) -> Option<&'static str> {
+        self.split_name().2
+    }
+
+    /// Runs the generator, leaving the solver ready to run
+    pub fn generate(&self, input: &str) -> Result<Box<dyn Runner>> {
+        let input = ArcStr::from(input);
+        let result = panic::catch_unwind(|| (self.factory)(input))
+            .map_err(|_| anyhow!("{} panicked while generating", self.name))?;
+        result.map_err(|e| anyhow!("{} failed while generating: {}", self.name, e))
+    }
+
+    /// Runs the solver on an already generated input
+    pub fn solve(&self, runner: &dyn Runner) -> Result<String> {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| runner.try_run()))
+            .map_err(|_| anyhow!("{} panicked while running", self.name))?;
+        result
+            .map(|answer| answer.to_string())
+            .map_err(|e| anyhow!("{} failed while running: {}", self.name, e))
+    }
+
+    pub fn run(&self, input: &str) -> Result<Outcome> {
+        let start = Instant::now();
+        let runner = self.generate(input)?;
+        let generated = Instant::now();
+        let answer = self.solve(runner.as_ref())?;
+        let finished = Instant::now();
+        Ok(Outcome {
+            answer,
+            generator: generated - start,
+            runner: finished - generated,
+        })
+    }
+}
+
+/// Solutions for `day`, limited to `part` if given, in registration order
+pub fn find(day: u32, part: Option<u32>) -> impl Iterator<Item = &'static Solution> {
+    SOLUTIONS
+        .iter()
+        .filter(move |s| s.day() == day && part.map_or(true, |p| s.part() == p))
+}
+
+/// Every day with at least one solution, in order
+pub fn days() -> Vec<u32> {
+    let mut result: Vec<u32> = SOLUTIONS.iter().map(Solution::day).collect();
+    result.dedup();
+    result
+}
+
+/// Where `cargo aoc` downloads the input for `day` to
+pub fn input_path(day: u32) -> PathBuf {
+    PathBuf::from(format!("input/2022/day{}.txt", day))
+}
+
+/// Reads the input for `day` from `input_path(day)`
+pub fn default_input(day: u32) -> Result<String> {
+    let path = input_path(day);
+    std::fs::read_to_string(&path).with_context(|| format!("Could not read {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use itertools::Itertools;
+
+    use super::*;
+
+    #[test]
+    fn registry_test() -> Result<()> {
+        assert_eq!(days(), (1..=23).collect::<Vec<u32>>());
+        let fast = find(14, Some(1))
+            .find(|s| s.variant() == Some("fast"))
+            .context("No fast variant")?;
+        assert_eq!((fast.day(), fast.part()), (14, 1));
+        assert_eq!(find(12, None).count(), 6);
+
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        let answers = find(1, None)
+            .map(|s| s.run(input).map(|o| o.answer))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(answers, vec!["24000", "24000", "45000", "45000"]);
+        assert!(find(1, Some(1)).next().unwrap().run("abc").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn registered_test() {
+        const SOURCES: [&str; 23] = [
+            include_str!("day1.rs"),
+            include_str!("day2.rs"),
+            include_str!("day3.rs"),
+            include_str!("day4.rs"),
+            include_str!("day5.rs"),
+            include_str!("day6.rs"),
+            include_str!("day7.rs"),
+            include_str!("day8.rs"),
+            include_str!("day9.rs"),
+            include_str!("day10.rs"),
+            include_str!("day11.rs"),
+            include_str!("day12.rs"),
+            include_str!("day13.rs"),
+            include_str!("day14.rs"),
+            include_str!("day15.rs"),
+            include_str!("day16.rs"),
+            include_str!("day17.rs"),
+            include_str!("day18.rs"),
+            include_str!("day19.rs"),
+            include_str!("day20.rs"),
+            include_str!("day21.rs"),
+            include_str!("day22.rs"),
+            include_str!("day23.rs"),
+        ];
+        let annotated: BTreeSet<String> = SOURCES
+            .iter()
+            .flat_map(|source| source.lines())
+            .filter_map(|l| l.trim().strip_prefix("#[aoc(")?.strip_suffix(")]"))
+            .map(|args| args.split(',').map(str::trim).join("_"))
+            .collect();
+        let registered: BTreeSet<String> = SOLUTIONS.iter().map(|s| s.name().to_string()).collect();
+        assert_eq!(registered, annotated);
+    }
+}
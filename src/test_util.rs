@@ -0,0 +1,19 @@
+//! Helpers shared between the test modules of several days.
+
+/// Small linear congruential generator so that randomised test fixtures are reproducible
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// The top 31 bits of the next state, which are the best distributed
+    pub fn next_u31(&mut self) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as u32
+    }
+}
@@ -0,0 +1,144 @@
+//! Checks every solution against answers recorded for the real puzzle inputs.
+//!
+//! Inputs live in `input/2022/dayN.txt` and the answers in `input/2022/answers.txt`, neither of which is committed.
+//! The answers file has one `dayN_partM: answer` line per part, and every variant of a part must give that answer.
+//! Days without an input or parts without an answer are skipped.
+//!
+//! This is slow in debug builds, so run it with `cargo test --release --test answers -- --ignored`.
+//! Setting `RECORD_ANSWERS=1` writes the answers file from the current solutions instead of checking it.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use aoc_2022::registry::{self, Solution};
+
+const ANSWERS: &str = "input/2022/answers.txt";
+
+type Answers = BTreeMap<String, String>;
+
+fn key(solution: &Solution) -> String {
+    format!("day{}_part{}", solution.day(), solution.part())
+}
+
+fn read_answers(path: &Path) -> Result<Answers> {
+    let text = fs::read_to_string(path)?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(idx, l)| {
+            let (key, answer) = l
+                .split_once(':')
+                .with_context(|| format!("Line {}: Expected dayN_partM: answer", idx + 1))?;
+            Ok((key.trim().to_string(), answer.trim().to_string()))
+        })
+        .collect()
+}
+
+fn write_answers(path: &Path, answers: &Answers) -> Result<()> {
+    let text: String = answers
+        .iter()
+        .map(|(key, answer)| format!("{}: {}\n", key, answer))
+        .collect();
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Runs every solution which has an input, pairing each with its answer
+fn run_all() -> Vec<(&'static Solution, Result<String>)> {
+    let mut result = vec![];
+    for day in registry::days() {
+        if !registry::input_path(day).exists() {
+            println!("Skipping day {}: no input", day);
+            continue;
+        }
+        let input = registry::default_input(day);
+        for solution in registry::find(day, None) {
+            let answer = match &input {
+                Ok(input) => solution.run(input).map(|o| o.answer),
+                Err(e) => Err(anyhow!("{:#}", e)),
+            };
+            result.push((solution, answer));
+        }
+    }
+    result
+}
+
+fn record(path: &Path) -> Result<()> {
+    let mut answers = Answers::new();
+    for (solution, answer) in run_all() {
+        // Only the main solution is recorded; the variants are checked against it later
+        if solution.variant().is_some() {
+            continue;
+        }
+        match answer {
+            Ok(answer) => {
+                answers.insert(key(solution), answer);
+            }
+            Err(e) => println!("Not recording {:#}", e),
+        }
+    }
+    write_answers(path, &answers)?;
+    println!("Recorded {} answers to {}", answers.len(), path.display());
+    Ok(())
+}
+
+#[test]
+#[ignore]
+fn recorded_answers() -> Result<()> {
+    let path = Path::new(ANSWERS);
+    if std::env::var_os("RECORD_ANSWERS").is_some() {
+        return record(path);
+    }
+    if !path.exists() {
+        println!("Skipping: no answers in {}", path.display());
+        return Ok(());
+    }
+
+    let answers = read_answers(path)?;
+    let mut failures = vec![];
+    let mut checked = 0;
+    for (solution, answer) in run_all() {
+        let Some(expected) = answers.get(&key(solution)) else {
+            println!("Skipping {}: no recorded answer", solution.name());
+            continue;
+        };
+        checked += 1;
+        match answer {
+            Ok(answer) if answer == *expected => {}
+            Ok(answer) => failures.push(format!(
+                "{}: expected {} but got {}",
+                solution.name(),
+                expected,
+                answer
+            )),
+            Err(e) => failures.push(format!("{:#}", e)),
+        }
+    }
+
+    println!("Checked {} solutions", checked);
+    if !failures.is_empty() {
+        bail!("{} wrong answers\n{}", failures.len(), failures.join("\n"));
+    }
+    Ok(())
+}
+
+#[test]
+fn answers_file_test() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("aoc_2022_answers_{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("answers.txt");
+
+    let answers: Answers = [("day1_part1", "24000"), ("day10_part2", "EHPZPJGL")]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    write_answers(&path, &answers)?;
+    assert_eq!(read_answers(&path)?, answers);
+
+    fs::write(&path, "day1_part1: 1\n\nday1_part2 2\n")?;
+    let err = read_answers(&path).unwrap_err();
+    assert_eq!(format!("{}", err), "Line 3: Expected dayN_partM: answer");
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}